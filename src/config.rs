@@ -1,5 +1,16 @@
 use serde::Deserialize;
-use std::{collections::HashMap, fs::File, io::Read, path::PathBuf};
+use std::{
+    collections::HashMap,
+    fs::{self, File},
+    io::Read,
+    path::PathBuf,
+    sync::Mutex,
+};
+use toml_edit::{Array, DocumentMut};
+
+static PATH: &str = "config.toml";
+/// Keeps concurrent writes to the config file from overwriting each other.
+static WRITE: Mutex<()> = Mutex::new(());
 
 #[derive(Debug, Deserialize)]
 pub struct Config {
@@ -12,6 +23,10 @@ pub struct Base {
     pub bind: String,
     pub cert: PathBuf,
     pub key: PathBuf,
+    /// Lets visitors without a registered certificate browse public
+    /// profiles, posts, hashtags and feeds.
+    #[serde(default)]
+    pub guest: bool,
 }
 
 #[derive(Debug, Deserialize)]
@@ -19,14 +34,47 @@ pub struct Account {
     pub pds: String,
     pub username: String,
     pub password: String,
+    /// Default languages for new posts.
+    #[serde(default)]
+    pub langs: Vec<String>,
+    /// Hashtags pinned to the home page.
+    #[serde(default)]
+    pub tags: Vec<String>,
+    /// Saved search queries.
+    #[serde(default)]
+    pub searches: Vec<String>,
 }
 
 impl Config {
     pub fn parse() -> Result<Config, Box<dyn std::error::Error>> {
-        let mut file = File::open("config.toml")?;
+        let mut file = File::open(PATH)?;
         let mut contents = String::new();
         file.read_to_string(&mut contents)?;
 
         Ok(toml::from_str(&contents)?)
     }
+
+    /// Writes one of an account's settings back to the config file, so that
+    /// it survives restarts. The rest of the file is kept as it is.
+    pub fn store(
+        fingerprint: &str,
+        key: &str,
+        values: &[String],
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        let _write = WRITE.lock().map_err(|_| "config lock poisoned")?;
+        let mut document = fs::read_to_string(PATH)?.parse::<DocumentMut>()?;
+
+        let account = document
+            .get_mut("accounts")
+            .and_then(|a| a.get_mut(fingerprint))
+            .and_then(|a| a.as_table_like_mut())
+            .ok_or("account not found in config")?;
+        account.insert(
+            key,
+            toml_edit::value(values.iter().map(String::as_str).collect::<Array>()),
+        );
+
+        fs::write(PATH, document.to_string())?;
+        Ok(())
+    }
 }
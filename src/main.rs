@@ -1,4 +1,5 @@
 mod config;
+mod moderation;
 mod session;
 mod state;
 mod types;
@@ -31,10 +32,81 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
         .state(state)
         .route("/", crate::views::feed)
         .route("/p", crate::views::post)
+        .route("/sub", crate::views::gemsub)
+        .route("/atom", crate::views::atom)
+        .route("/p/o", crate::views::post_options)
+        .route("/p/o/:options", crate::views::post)
         .route("/p/:id", crate::views::interact)
         .route("/p/:id/r", crate::views::reply)
+        .route("/p/:id/r/:options", crate::views::reply)
+        .route("/p/:id/o", crate::views::post_options)
+        .route("/p/:id/g", crate::views::gate)
+        .route("/p/:id/v", crate::views::show)
+        .route("/p/:id/m", crate::views::mute_thread)
+        .route("/p/:id/x", crate::views::report)
+        .route("/p/:id/x/:reason", crate::views::report)
+        .route("/f", crate::views::feeds)
+        .route("/f/open", crate::views::open_feed)
+        .route("/d", crate::views::discover)
+        .route("/d/s", crate::views::search_feeds)
+        .route("/f/:did/:rkey", crate::views::custom_feed)
+        .route("/f/:did/:rkey/s", crate::views::save_feed)
+        .route("/f/:did/:rkey/p", crate::views::pin_feed)
+        .route("/f/:did/:rkey/l", crate::views::like_feed)
+        .route("/f/:did/:rkey/sub", crate::views::gemsub)
+        .route("/f/:did/:rkey/atom", crate::views::atom)
+        .route("/ls", crate::views::lists)
+        .route("/ls/o", crate::views::open_list)
+        .route("/ls/n", crate::views::new_list)
+        .route("/ls/n/:purpose", crate::views::new_list)
+        .route("/ls/:did/:rkey", crate::views::list)
+        .route("/ls/:did/:rkey/d", crate::views::delete_list)
+        .route("/ls/:did/:rkey/m", crate::views::mute_list)
+        .route("/ls/:did/:rkey/b", crate::views::block_list)
+        .route("/ls/:did/:rkey/sp", crate::views::new_starter_pack)
+        .route("/sp/:did/:rkey", crate::views::starter_pack)
+        .route("/sp/:did/:rkey/f", crate::views::follow_all)
+        .route("/l", crate::views::langs)
+        .route("/s", crate::views::search)
+        .route("/s/saved", crate::views::searches)
+        .route("/s/save", crate::views::save_search)
+        .route("/s/:sort", crate::views::search)
+        .route("/s/:sort/more", crate::views::search)
+        .route("/t/:tag", crate::views::tag)
+        .route("/t/:tag/f", crate::views::follow_tag)
+        .route("/t/:tag/:sort", crate::views::tag)
         .route("/@:profile", crate::views::profile)
         .route("/@:profile/f", crate::views::follow)
+        .route("/@:profile/s", crate::views::subscribe)
+        .route("/@:profile/:kind", crate::views::connections)
+        .route("/@:profile/l", crate::views::lists)
+        .route("/@:profile/l/:rkey", crate::views::list_member)
+        .route("/@:profile/dm", crate::views::message)
+        .route("/@:profile/p/:rkey", crate::views::permalink)
+        .route("/@:profile/p/:rkey/quotes", crate::views::quotes)
+        .route("/@:profile/p/:rkey/:kind", crate::views::engagement)
+        .route("/@:profile/sub", crate::views::gemsub)
+        .route("/@:profile/atom", crate::views::atom)
+        .route("/@:profile/m", crate::views::mute)
+        .route("/@:profile/b", crate::views::block)
+        .route("/@:profile/x", crate::views::report)
+        .route("/@:profile/x/:reason", crate::views::report)
+        .route("/n", crate::views::notifications)
+        .route("/n/:filter", crate::views::notifications)
+        .route("/dm", crate::views::convos)
+        .route("/dm/c/:convo", crate::views::convo)
+        .route("/dm/c/:convo/s", crate::views::send_message)
+        .route("/dm/c/:convo/r", crate::views::mark_read)
+        .route("/e", crate::views::edit_profile)
+        .route("/e/name", crate::views::edit_name)
+        .route("/e/bio", crate::views::edit_bio)
+        .titan("/e/:image", crate::views::upload_image, 1_000_000)
+        .route("/m", crate::views::moderation)
+        .route("/m/muted", crate::views::muted)
+        .route("/m/blocked", crate::views::blocked)
+        .route("/m/words", crate::views::words)
+        .route("/m/words/a", crate::views::mute_word)
+        .route("/m/words/r", crate::views::unmute_word)
         .run();
 
     info!("listening on {}", config.base.bind);
@@ -0,0 +1,392 @@
+use std::collections::HashMap;
+
+use atrium_api::{
+    app::bsky::actor::defs::{MutedWord, PreferencesItem},
+    com::atproto::label::defs::Label,
+    types::{
+        string::{Datetime, Did},
+        Union,
+    },
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, PartialOrd)]
+pub enum Visibility {
+    Show,
+    Warn,
+    Hide,
+}
+
+impl Visibility {
+    fn parse(visibility: &str) -> Visibility {
+        match visibility {
+            "hide" => Visibility::Hide,
+            "warn" => Visibility::Warn,
+            _ => Visibility::Show,
+        }
+    }
+}
+
+/// Content label settings and muted words, as read from the user's
+/// preferences.
+#[derive(Debug, Default, Clone)]
+pub struct Moderation {
+    adult: bool,
+    labels: HashMap<(Option<String>, String), Visibility>,
+    words: Vec<MutedWord>,
+    guest: bool,
+}
+
+impl Moderation {
+    pub fn from_preferences(preferences: &[Union<PreferencesItem>]) -> Moderation {
+        let mut moderation = Moderation::default();
+
+        for preference in preferences {
+            match preference {
+                Union::Refs(PreferencesItem::AdultContentPref(pref)) => {
+                    moderation.adult = pref.enabled;
+                }
+                Union::Refs(PreferencesItem::ContentLabelPref(pref)) => {
+                    moderation.labels.insert(
+                        (
+                            pref.labeler_did.as_ref().map(|did| did.to_string()),
+                            pref.label.clone(),
+                        ),
+                        Visibility::parse(&pref.visibility),
+                    );
+                }
+                Union::Refs(PreferencesItem::MutedWordsPref(pref)) => {
+                    moderation.words.extend(pref.items.iter().cloned());
+                }
+                _ => (),
+            }
+        }
+
+        moderation
+    }
+
+    /// Moderation for visitors who aren't signed in, who can't see content
+    /// its authors chose to keep from them.
+    pub fn guest() -> Moderation {
+        Moderation {
+            guest: true,
+            ..Default::default()
+        }
+    }
+
+    /// Whether this is the moderation for visitors who aren't signed in.
+    pub fn is_guest(&self) -> bool {
+        self.guest
+    }
+
+    /// Decides how a single label should be treated. Settings made for the
+    /// labeler which applied it take precedence over global ones.
+    pub fn label(&self, src: &str, val: &str) -> Visibility {
+        let adult = matches!(val, "porn" | "sexual" | "nudity");
+        if adult && !self.adult {
+            return Visibility::Hide;
+        }
+
+        if let Some(visibility) = self
+            .labels
+            .get(&(Some(src.to_string()), val.to_string()))
+            .or_else(|| self.labels.get(&(None, val.to_string())))
+        {
+            return *visibility;
+        }
+
+        match val {
+            "!hide" => Visibility::Hide,
+            "!warn" => Visibility::Warn,
+            "nudity" => Visibility::Show,
+            "!no-unauthenticated" if self.guest => Visibility::Hide,
+            // Other system labels, such as "!no-unauthenticated", don't apply
+            // to signed in users.
+            v if v.starts_with('!') => Visibility::Show,
+            _ => Visibility::Warn,
+        }
+    }
+
+    /// Returns the values of the labels which apply, along with how the
+    /// labelled content should be treated.
+    pub fn labels(&self, labels: &[Label]) -> (Vec<String>, Visibility) {
+        let mut values = Vec::new();
+        let mut visibility = Visibility::Show;
+
+        for label in labels.iter().filter(|l| !l.neg.unwrap_or(false)) {
+            if !label.val.starts_with('!') && !values.contains(&label.val) {
+                values.push(label.val.clone());
+            }
+
+            let action = self.label(label.src.as_str(), &label.val);
+            if action > visibility {
+                visibility = action;
+            }
+        }
+
+        (values, visibility)
+    }
+
+    /// Checks a post's text and tags against the muted words. Words which
+    /// only apply to accounts the user doesn't follow are skipped when
+    /// `following` is set.
+    pub fn muted(&self, text: &str, tags: &[String], following: bool) -> bool {
+        let now = Datetime::now();
+        let text = text.to_lowercase();
+
+        self.words.iter().any(|word| {
+            if word.expires_at.as_ref().is_some_and(|e| e <= &now) {
+                return false;
+            }
+            if following && word.actor_target.as_deref() == Some("exclude-following") {
+                return false;
+            }
+
+            let value = word.value.trim_start_matches(['#', '＃']).to_lowercase();
+            if value.is_empty() {
+                return false;
+            }
+            if tags.iter().any(|t| t.to_lowercase() == value) {
+                return true;
+            }
+            if !word.targets.iter().any(|t| t == "content") {
+                return false;
+            }
+
+            // Single words must match a whole word, while phrases can appear
+            // anywhere in the text.
+            if value.chars().all(char::is_alphanumeric) {
+                text.split(|c: char| !c.is_alphanumeric())
+                    .any(|w| w == value)
+            } else {
+                text.contains(&value)
+            }
+        })
+    }
+}
+
+/// Returns the user's muted words.
+pub fn muted_words(preferences: &[Union<PreferencesItem>]) -> Vec<MutedWord> {
+    preferences
+        .iter()
+        .filter_map(|p| match p {
+            Union::Refs(PreferencesItem::MutedWordsPref(pref)) => Some(pref.items.clone()),
+            _ => None,
+        })
+        .flatten()
+        .collect()
+}
+
+/// Returns the labelers the user has subscribed to.
+pub fn labelers(preferences: &[Union<PreferencesItem>]) -> Vec<Did> {
+    preferences
+        .iter()
+        .filter_map(|p| match p {
+            Union::Refs(PreferencesItem::LabelersPref(pref)) => Some(
+                pref.labelers
+                    .iter()
+                    .map(|l| l.did.clone())
+                    .collect::<Vec<_>>(),
+            ),
+            _ => None,
+        })
+        .flatten()
+        .collect()
+}
+
+/// Maps the short names used in report prompts to moderation reason types.
+pub fn reason_type(reason: &str) -> Option<&'static str> {
+    use atrium_api::com::atproto::moderation::defs;
+
+    match reason {
+        "spam" => Some(defs::REASON_SPAM),
+        "violation" => Some(defs::REASON_VIOLATION),
+        "misleading" => Some(defs::REASON_MISLEADING),
+        "sexual" => Some(defs::REASON_SEXUAL),
+        "rude" => Some(defs::REASON_RUDE),
+        "other" => Some(defs::REASON_OTHER),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use atrium_api::{
+        app::bsky::actor::defs::{
+            AdultContentPrefData, ContentLabelPrefData, MutedWordData, MutedWordsPrefData,
+        },
+        com::atproto::label::defs::LabelData,
+        types::Object,
+    };
+
+    const LABELER: &str = "did:plc:labeler";
+    const OTHER: &str = "did:plc:other";
+
+    fn label_pref(labeler: Option<&str>, label: &str, visibility: &str) -> Union<PreferencesItem> {
+        Union::Refs(PreferencesItem::ContentLabelPref(Box::new(Object::from(
+            ContentLabelPrefData {
+                label: label.to_string(),
+                labeler_did: labeler.map(|did| Did::new(did.to_string()).unwrap()),
+                visibility: visibility.to_string(),
+            },
+        ))))
+    }
+
+    fn adult(enabled: bool) -> Union<PreferencesItem> {
+        Union::Refs(PreferencesItem::AdultContentPref(Box::new(Object::from(
+            AdultContentPrefData { enabled },
+        ))))
+    }
+
+    fn label(val: &str, neg: bool) -> Label {
+        Object::from(LabelData {
+            cid: None,
+            cts: Datetime::now(),
+            exp: None,
+            neg: Some(neg),
+            sig: None,
+            src: Did::new(LABELER.to_string()).unwrap(),
+            uri: String::from("at://did:plc:author/app.bsky.feed.post/1"),
+            val: val.to_string(),
+            ver: None,
+        })
+    }
+
+    #[test]
+    fn labeler_settings_override_global_ones() {
+        let moderation = Moderation::from_preferences(&[
+            label_pref(None, "gore", "hide"),
+            label_pref(Some(LABELER), "gore", "ignore"),
+        ]);
+
+        assert_eq!(moderation.label(LABELER, "gore"), Visibility::Show);
+        assert_eq!(moderation.label(OTHER, "gore"), Visibility::Hide);
+    }
+
+    #[test]
+    fn unknown_labels_warn() {
+        let moderation = Moderation::default();
+
+        assert_eq!(moderation.label(LABELER, "spoiler"), Visibility::Warn);
+        assert_eq!(moderation.label(LABELER, "!hide"), Visibility::Hide);
+        assert_eq!(moderation.label(LABELER, "!warn"), Visibility::Warn);
+    }
+
+    #[test]
+    fn adult_content_is_hidden_unless_enabled() {
+        let disabled = Moderation::from_preferences(&[label_pref(None, "porn", "ignore")]);
+        assert_eq!(disabled.label(LABELER, "porn"), Visibility::Hide);
+        assert_eq!(disabled.label(LABELER, "nudity"), Visibility::Hide);
+
+        let enabled =
+            Moderation::from_preferences(&[adult(true), label_pref(None, "porn", "ignore")]);
+        assert_eq!(enabled.label(LABELER, "porn"), Visibility::Show);
+        assert_eq!(enabled.label(LABELER, "sexual"), Visibility::Warn);
+        assert_eq!(enabled.label(LABELER, "nudity"), Visibility::Show);
+    }
+
+    #[test]
+    fn system_labels_are_not_listed() {
+        let moderation = Moderation::default();
+        let (values, visibility) =
+            moderation.labels(&[label("!no-unauthenticated", false), label("spoiler", false)]);
+
+        assert_eq!(values, vec![String::from("spoiler")]);
+        assert_eq!(visibility, Visibility::Warn);
+    }
+
+    #[test]
+    fn negated_labels_are_ignored() {
+        let moderation = Moderation::default();
+        let (values, visibility) =
+            moderation.labels(&[label("!hide", true), label("spoiler", true)]);
+
+        assert!(values.is_empty());
+        assert_eq!(visibility, Visibility::Show);
+    }
+
+    #[test]
+    fn strictest_label_wins() {
+        let moderation = Moderation::from_preferences(&[label_pref(None, "gore", "hide")]);
+        let (values, visibility) =
+            moderation.labels(&[label("spoiler", false), label("gore", false)]);
+
+        assert_eq!(values, vec![String::from("spoiler"), String::from("gore")]);
+        assert_eq!(visibility, Visibility::Hide);
+    }
+
+    #[test]
+    fn guests_cant_see_posts_kept_from_them() {
+        let label = label("!no-unauthenticated", false);
+
+        assert_eq!(
+            Moderation::guest().labels(std::slice::from_ref(&label)).1,
+            Visibility::Hide
+        );
+        assert_eq!(Moderation::default().labels(&[label]).1, Visibility::Show);
+    }
+
+    fn word(value: &str, targets: &[&str]) -> MutedWordData {
+        MutedWordData {
+            actor_target: None,
+            expires_at: None,
+            id: None,
+            targets: targets.iter().map(|t| t.to_string()).collect(),
+            value: value.to_string(),
+        }
+    }
+
+    fn words(items: Vec<MutedWordData>) -> Moderation {
+        Moderation::from_preferences(&[Union::Refs(PreferencesItem::MutedWordsPref(Box::new(
+            Object::from(MutedWordsPrefData {
+                items: items.into_iter().map(Object::from).collect(),
+            }),
+        )))])
+    }
+
+    #[test]
+    fn single_words_match_whole_words() {
+        let moderation = words(vec![word("cat", &["content"])]);
+
+        assert!(moderation.muted("My CAT, sleeping", &[], false));
+        assert!(!moderation.muted("concatenate", &[], false));
+    }
+
+    #[test]
+    fn phrases_match_anywhere() {
+        let moderation = words(vec![word("new york", &["content"])]);
+
+        assert!(moderation.muted("Flying to New York tomorrow", &[], false));
+        assert!(!moderation.muted("York is new", &[], false));
+    }
+
+    #[test]
+    fn tags_match_with_or_without_hash() {
+        let moderation = words(vec![word("#Rust", &["tag"])]);
+
+        assert!(moderation.muted("", &[String::from("rust")], false));
+        assert!(!moderation.muted("I like rust", &[], false));
+    }
+
+    #[test]
+    fn expired_words_are_skipped() {
+        let mut old = word("old", &["content"]);
+        old.expires_at = Some("2000-01-01T00:00:00.000Z".parse().unwrap());
+        let mut new = word("new", &["content"]);
+        new.expires_at = Some("2999-01-01T00:00:00.000Z".parse().unwrap());
+        let moderation = words(vec![old, new]);
+
+        assert!(!moderation.muted("old news", &[], false));
+        assert!(moderation.muted("new news", &[], false));
+    }
+
+    #[test]
+    fn exclude_following_skips_followed_accounts() {
+        let mut spoiler = word("spoiler", &["content"]);
+        spoiler.actor_target = Some(String::from("exclude-following"));
+        let moderation = words(vec![spoiler]);
+
+        assert!(moderation.muted("spoiler ahead", &[], false));
+        assert!(!moderation.muted("spoiler ahead", &[], true));
+    }
+}
@@ -1,33 +1,69 @@
-use std::{collections::HashMap, str::FromStr, sync::Arc};
+use std::{
+    collections::{BTreeMap, HashMap},
+    str::FromStr,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc,
+    },
+};
 
 use atrium_api::{
-    agent::{store::MemorySessionStore, AtpAgent},
+    agent::{
+        bluesky::{AtprotoServiceType, BSKY_CHAT_DID, BSKY_LABELER_DID},
+        store::MemorySessionStore,
+        AtpAgent,
+    },
+    app::bsky::{
+        actor::defs::{PreferencesItem, SavedFeedData},
+        feed::defs::FeedViewPostReasonRefs,
+        labeler::get_services::OutputViewsItem,
+    },
+    chat::bsky::convo::get_messages::OutputMessagesItem,
     com::atproto::repo::strong_ref::MainData,
     record::KnownRecord,
     types::{
-        string::{AtIdentifier, Datetime, Did, Nsid},
-        Collection, LimitedNonZeroU8, Object, TryFromUnknown, TryIntoUnknown,
+        string::{AtIdentifier, Datetime, Did, Handle, Language, Nsid},
+        BlobRef, Collection, LimitedNonZeroU8, Object, TryFromUnknown, TryIntoUnknown, Union,
+        UnknownData,
     },
+    xrpc::error::{XrpcError, XrpcErrorKind},
 };
 use atrium_xrpc_client::reqwest::ReqwestClient;
+use chrono::TimeDelta;
 use futures::future::join_all;
+use ipld_core::ipld::Ipld;
 use tokio::sync::Mutex;
 
 use crate::{
-    config::Account,
-    types::{Post, Profile},
+    config::{Account, Config},
+    moderation::{self, Moderation, Visibility},
+    types::{
+        at_uri, Actor, Convo, Generator, List, ListPurpose, Message, Notification, Post,
+        PostOptions, Profile, ProfileEdit, Query, ReplyRule, Search, StarterPack, Word,
+    },
 };
 
+/// The AppView used by guests, which serves public data without signing in.
+static PUBLIC_APPVIEW: &str = "https://public.api.bsky.app";
+
 #[derive(Clone)]
 pub struct Session {
     id: AtIdentifier,
     agent: Arc<AtpAgent<MemorySessionStore, ReqwestClient>>,
     objects: Arc<Mutex<HashMap<String, MainData>>>,
+    langs: Arc<Mutex<Vec<String>>>,
+    tags: Arc<Mutex<Vec<String>>>,
+    searches: Arc<Mutex<Vec<String>>>,
+    /// The user's key in the config, under which their settings are saved.
+    fingerprint: String,
     pub handle: String,
+    /// Whether this is the read-only session used for visitors.
+    guest: bool,
 }
 
 impl Session {
     pub async fn new(
+        fingerprint: &str,
         account: &Account,
         objects: Arc<Mutex<HashMap<String, MainData>>>,
     ) -> Result<Session, Box<dyn std::error::Error>> {
@@ -40,15 +76,53 @@ impl Session {
         let session = agent.api.com.atproto.server.get_session().await?;
         let id = AtIdentifier::Did(session.did.clone());
 
-        Ok(Session {
+        let session = Session {
             id,
             agent: Arc::new(agent),
             objects,
+            langs: Arc::new(Mutex::new(account.langs.clone())),
+            tags: Arc::new(Mutex::new(account.tags.clone())),
+            searches: Arc::new(Mutex::new(account.searches.clone())),
+            fingerprint: fingerprint.to_string(),
             handle: session.handle.to_string(),
-        })
+            guest: false,
+        };
+        // Fetching the preferences sets up the labelers used for every
+        // following request.
+        session.preferences().await?;
+
+        Ok(session)
+    }
+
+    /// Creates a read-only session for visitors, which talks to the public
+    /// AppView without signing in.
+    pub fn guest(
+        objects: Arc<Mutex<HashMap<String, MainData>>>,
+    ) -> Result<Session, Box<dyn std::error::Error>> {
+        let agent = AtpAgent::new(
+            ReqwestClient::new(PUBLIC_APPVIEW),
+            MemorySessionStore::default(),
+        );
+
+        let session = Session {
+            // Guests have no repository, so anything writing to one fails.
+            id: AtIdentifier::Handle(Handle::new(String::from("handle.invalid"))?),
+            agent: Arc::new(agent),
+            objects,
+            langs: Arc::new(Mutex::new(Vec::new())),
+            tags: Arc::new(Mutex::new(Vec::new())),
+            searches: Arc::new(Mutex::new(Vec::new())),
+            fingerprint: String::new(),
+            handle: String::from("handle.invalid"),
+            guest: true,
+        };
+        session.configure_labelers(&[])?;
+
+        Ok(session)
     }
 
     pub async fn feed(self) -> Result<Vec<Post>, Box<dyn std::error::Error>> {
+        let moderation = self.moderation(&self.preferences().await?);
         let action = self
             .agent
             .api
@@ -64,343 +138,2909 @@ impl Session {
             ))
             .await?;
 
-        let feed: Vec<Post> = join_all(
-            action
-                .feed
-                .iter()
-                .map(|v| async { Post::push(v, &self.objects).await }),
-        )
-        .await;
+        let feed = self.push_feed(&action.feed, &moderation).await;
         Ok(feed)
     }
 
-    pub async fn profile<'a>(self, id: &'a str) -> Result<Profile, Box<dyn std::error::Error>> {
-        let identifier = AtIdentifier::from_str(id)?;
-        let account = self
+    /// Lists the feed generators saved by the user, in the order they were
+    /// saved in.
+    pub async fn feeds(self) -> Result<Vec<Generator>, Box<dyn std::error::Error>> {
+        let saved = saved_feeds(&self.preferences().await?);
+        let uris: Vec<String> = saved
+            .iter()
+            .filter(|s| s.r#type == "feed")
+            .map(|s| s.value.clone())
+            .collect();
+
+        self.generators(uris, &saved).await
+    }
+
+    /// Lists feeds suggested to the user, falling back to popular ones when
+    /// there are no suggestions.
+    pub async fn suggested_feeds(self) -> Result<Vec<Generator>, Box<dyn std::error::Error>> {
+        let saved = saved_feeds(&self.preferences().await?);
+        let suggested = self
             .agent
             .api
             .app
             .bsky
-            .actor
-            .get_profile(Object::from(
-                atrium_api::app::bsky::actor::get_profile::ParametersData {
-                    actor: identifier.clone(),
+            .feed
+            .get_suggested_feeds(Object::from(
+                atrium_api::app::bsky::feed::get_suggested_feeds::ParametersData {
+                    cursor: None,
+                    limit: LimitedNonZeroU8::try_from(10).ok(),
                 },
             ))
             .await?;
-        let account_feed = self
+
+        if suggested.feeds.is_empty() {
+            return self.popular_feeds(None).await;
+        }
+
+        Ok(suggested
+            .feeds
+            .iter()
+            .map(|view| Generator::new(view, &saved))
+            .collect())
+    }
+
+    pub async fn popular_feeds(
+        self,
+        query: Option<&str>,
+    ) -> Result<Vec<Generator>, Box<dyn std::error::Error>> {
+        let saved = saved_feeds(&self.preferences().await?);
+        let popular = self
             .agent
             .api
             .app
             .bsky
-            .feed
-            .get_author_feed(Object::from(
-                atrium_api::app::bsky::feed::get_author_feed::ParametersData {
-                    actor: identifier.clone(),
+            .unspecced
+            .get_popular_feed_generators(Object::from(
+                atrium_api::app::bsky::unspecced::get_popular_feed_generators::ParametersData {
                     cursor: None,
-                    filter: None,
-                    include_pins: Some(true),
                     limit: LimitedNonZeroU8::try_from(10).ok(),
+                    query: query.map(String::from),
                 },
             ))
             .await?;
 
-        Ok(Profile {
-            id: account.handle.clone(),
-            name: account
-                .display_name
-                .clone()
-                .unwrap_or(account.handle.to_string()),
-            bio: account.description.clone().unwrap_or("".to_string()),
-            followers: account.followers_count.unwrap_or(0) as u64,
-            follows: account.follows_count.unwrap_or(0) as u64,
-            following: account.viewer.clone().unwrap().following.is_some(),
-            posts: join_all(
-                account_feed
-                    .feed
-                    .iter()
-                    .map(|p| async { Post::push(p, &self.objects).await }),
-            )
-            .await,
-        })
+        Ok(popular
+            .feeds
+            .iter()
+            .map(|view| Generator::new(view, &saved))
+            .collect())
     }
 
-    pub async fn follow<'a>(self, id: &'a str) -> Result<(), Box<dyn std::error::Error>> {
-        let identifier = AtIdentifier::from_str(id)?;
-        let account = self
+    pub async fn suggested_accounts(self) -> Result<Vec<Actor>, Box<dyn std::error::Error>> {
+        Ok(self
             .agent
             .api
             .app
             .bsky
             .actor
-            .get_profile(Object::from(
-                atrium_api::app::bsky::actor::get_profile::ParametersData {
-                    actor: identifier.clone(),
+            .get_suggestions(Object::from(
+                atrium_api::app::bsky::actor::get_suggestions::ParametersData {
+                    cursor: None,
+                    limit: LimitedNonZeroU8::try_from(10).ok(),
                 },
             ))
-            .await?;
-        let following = account.viewer.clone().unwrap().following.clone();
+            .await?
+            .data
+            .actors
+            .iter()
+            .map(Actor::from_profile)
+            .collect())
+    }
 
-        if let Some(uri) = following {
-            self.agent
-                .api
-                .com
-                .atproto
-                .repo
-                .delete_record(Object::from(
-                    atrium_api::com::atproto::repo::delete_record::InputData {
-                        collection: Nsid::from_str(atrium_api::app::bsky::graph::Follow::NSID)?,
-                        repo: self.id.clone(),
-                        rkey: uri.split("/").last().unwrap().to_string(),
-                        swap_commit: None,
-                        swap_record: None,
-                    },
-                ))
-                .await?;
-        } else {
-            self.agent
-                .api
-                .com
-                .atproto
-                .repo
-                .create_record(Object::from(
-                    atrium_api::com::atproto::repo::create_record::InputData {
-                        collection: Nsid::from_str(atrium_api::app::bsky::graph::Follow::NSID)?,
-                        record: atrium_api::app::bsky::graph::follow::RecordData {
-                            created_at: Datetime::now(),
-                            subject: account.did.clone(),
-                        }
-                        .try_into_unknown()?,
-                        repo: self.id.clone(),
-                        rkey: None,
-                        swap_commit: None,
-                        validate: None,
-                    },
-                ))
-                .await?;
-        }
+    pub async fn custom_feed(
+        self,
+        did: &str,
+        rkey: &str,
+        cursor: Option<String>,
+    ) -> Result<(Generator, Vec<Post>, Option<String>), Box<dyn std::error::Error>> {
+        let preferences = self.preferences().await?;
+        let moderation = self.moderation(&preferences);
+        let uri = generator_uri(did, rkey);
+        let generator = self
+            .agent
+            .api
+            .app
+            .bsky
+            .feed
+            .get_feed_generator(Object::from(
+                atrium_api::app::bsky::feed::get_feed_generator::ParametersData {
+                    feed: uri.clone(),
+                },
+            ))
+            .await?;
+        let feed = self
+            .agent
+            .api
+            .app
+            .bsky
+            .feed
+            .get_feed(Object::from(
+                atrium_api::app::bsky::feed::get_feed::ParametersData {
+                    cursor,
+                    feed: uri,
+                    limit: Some(LimitedNonZeroU8::try_from(10)?),
+                },
+            ))
+            .await?;
 
-        Ok(())
+        Ok((
+            Generator::new(&generator.view, &saved_feeds(&preferences)),
+            self.push_feed(&feed.feed, &moderation).await,
+            feed.data.cursor,
+        ))
     }
 
-    pub async fn like<'a>(self, id: &'a str) -> Result<(), Box<dyn std::error::Error>> {
-        let hash_map = self.objects.lock().await;
-        let object = hash_map.get(id).unwrap();
-        let post = self
+    /// Lists an account's posts along with its own threads, leaving out
+    /// replies to others.
+    pub async fn author_feed(self, id: &str) -> Result<Vec<Post>, Box<dyn std::error::Error>> {
+        let moderation = self.moderation(&self.preferences().await?);
+        let feed = self
             .agent
             .api
             .app
             .bsky
             .feed
-            .get_posts(Object::from(
-                atrium_api::app::bsky::feed::get_posts::ParametersData {
-                    uris: vec![object.uri.clone()],
+            .get_author_feed(Object::from(
+                atrium_api::app::bsky::feed::get_author_feed::ParametersData {
+                    actor: AtIdentifier::from_str(id)?,
+                    cursor: None,
+                    filter: Some(String::from("posts_and_author_threads")),
+                    include_pins: None,
+                    limit: LimitedNonZeroU8::try_from(25).ok(),
                 },
             ))
             .await?;
 
-        if let Some(like) = post
-            .clone()
-            .posts
-            .first()
-            .unwrap()
-            .viewer
-            .clone()
-            .unwrap()
-            .like
-            .clone()
+        Ok(self.push_feed(&feed.feed, &moderation).await)
+    }
+
+    /// Finds the DID and record key behind an AT URI or a bsky.app link.
+    /// `kind` is the part of the link naming the record type, such as "feed"
+    /// or "lists".
+    pub async fn resolve_link(
+        self,
+        input: &str,
+        kind: &str,
+    ) -> Result<(String, String), Box<dyn std::error::Error>> {
+        let (authority, rkey) = if let Some(path) = input.strip_prefix("https://bsky.app/profile/")
         {
-            self.agent
-                .api
-                .com
-                .atproto
-                .repo
-                .delete_record(Object::from(
-                    atrium_api::com::atproto::repo::delete_record::InputData {
-                        collection: Nsid::from_str(atrium_api::app::bsky::feed::Like::NSID)?,
-                        repo: self.id.clone(),
-                        rkey: like.split('/').last().unwrap().to_string(),
-                        swap_commit: None,
-                        swap_record: None,
-                    },
-                ))
-                .await?;
+            match path.split('/').collect::<Vec<_>>()[..] {
+                [authority, k, rkey] if k == kind => (authority.to_string(), rkey.to_string()),
+                _ => return Err(format!("not a link to {kind}").into()),
+            }
         } else {
-            self.agent
-                .api
-                .com
-                .atproto
-                .repo
-                .create_record(Object::from(
-                    atrium_api::com::atproto::repo::create_record::InputData {
-                        collection: Nsid::from_str(atrium_api::app::bsky::feed::Like::NSID)?,
-                        record: atrium_api::record::KnownRecord::AppBskyFeedLike(Box::new(
-                            Object::from(atrium_api::app::bsky::feed::like::RecordData {
-                                created_at: atrium_api::types::string::Datetime::now(),
-                                subject: Object::from(object.clone()),
-                            }),
-                        ))
-                        .try_into_unknown()?,
-                        repo: self.id.clone(),
-                        rkey: None,
-                        swap_commit: None,
-                        validate: None,
-                    },
-                ))
-                .await?;
-        }
+            at_uri(input).ok_or("not an AT URI")?
+        };
 
-        Ok(())
+        let did = match authority.parse::<Did>() {
+            Ok(did) => did,
+            Err(_) => {
+                self.agent
+                    .api
+                    .com
+                    .atproto
+                    .identity
+                    .resolve_handle(Object::from(
+                        atrium_api::com::atproto::identity::resolve_handle::ParametersData {
+                            handle: authority.parse()?,
+                        },
+                    ))
+                    .await?
+                    .data
+                    .did
+            }
+        };
+
+        Ok((did.to_string(), rkey))
     }
 
-    pub async fn repost<'a>(self, id: &'a str) -> Result<(), Box<dyn std::error::Error>> {
-        let hash_map = self.objects.lock().await;
-        let object = hash_map.get(id).unwrap();
-        let post = self
+    /// Lists the lists made by an account.
+    pub async fn lists(self, id: &str) -> Result<Vec<List>, Box<dyn std::error::Error>> {
+        Ok(self
             .agent
             .api
             .app
             .bsky
-            .feed
-            .get_posts(Object::from(
-                atrium_api::app::bsky::feed::get_posts::ParametersData {
-                    uris: vec![object.uri.clone()],
+            .graph
+            .get_lists(Object::from(
+                atrium_api::app::bsky::graph::get_lists::ParametersData {
+                    actor: AtIdentifier::from_str(id)?,
+                    cursor: None,
+                    limit: LimitedNonZeroU8::try_from(50).ok(),
+                },
+            ))
+            .await?
+            .data
+            .lists
+            .iter()
+            .map(List::new)
+            .collect())
+    }
+
+    /// Returns a list along with its members, and its latest posts if it's a
+    /// curation list.
+    pub async fn list(
+        self,
+        did: &str,
+        rkey: &str,
+    ) -> Result<(List, Vec<Actor>, Vec<Post>), Box<dyn std::error::Error>> {
+        let uri = list_uri(did, rkey);
+        let list = self
+            .agent
+            .api
+            .app
+            .bsky
+            .graph
+            .get_list(Object::from(
+                atrium_api::app::bsky::graph::get_list::ParametersData {
+                    cursor: None,
+                    limit: LimitedNonZeroU8::try_from(50).ok(),
+                    list: uri.clone(),
                 },
             ))
             .await?;
+        let members = list
+            .items
+            .iter()
+            .map(|i| Actor::new(&i.subject.handle, &i.subject.display_name))
+            .collect();
+        let list = List::new(&list.data.list);
 
-        if let Some(repost) = post
-            .clone()
-            .posts
-            .first()
-            .unwrap()
-            .viewer
-            .clone()
-            .unwrap()
-            .repost
-            .clone()
-        {
-            self.agent
+        let posts = if list.purpose == ListPurpose::Curation {
+            let moderation = self.moderation(&self.preferences().await?);
+            let feed = self
+                .agent
                 .api
-                .com
-                .atproto
-                .repo
-                .delete_record(Object::from(
-                    atrium_api::com::atproto::repo::delete_record::InputData {
-                        collection: Nsid::from_str(atrium_api::app::bsky::feed::Like::NSID)?,
-                        repo: self.id.clone(),
-                        rkey: repost.split('/').last().unwrap().to_string(),
-                        swap_commit: None,
-                        swap_record: None,
+                .app
+                .bsky
+                .feed
+                .get_list_feed(Object::from(
+                    atrium_api::app::bsky::feed::get_list_feed::ParametersData {
+                        cursor: None,
+                        limit: LimitedNonZeroU8::try_from(10).ok(),
+                        list: uri,
                     },
                 ))
                 .await?;
+            self.push_feed(&feed.feed, &moderation).await
         } else {
-            self.agent
-                .api
-                .com
-                .atproto
-                .repo
-                .create_record(Object::from(
-                    atrium_api::com::atproto::repo::create_record::InputData {
-                        collection: Nsid::from_str(atrium_api::app::bsky::feed::Repost::NSID)?,
-                        record: atrium_api::record::KnownRecord::AppBskyFeedRepost(Box::new(
-                            Object::from(atrium_api::app::bsky::feed::repost::RecordData {
-                                created_at: atrium_api::types::string::Datetime::now(),
-                                subject: Object::from(object.clone()),
-                            }),
-                        ))
-                        .try_into_unknown()?,
-                        repo: self.id.clone(),
-                        rkey: None,
-                        swap_commit: None,
-                        validate: None,
-                    },
-                ))
-                .await?;
+            Vec::new()
+        };
+
+        Ok((list, members, posts))
+    }
+
+    /// Returns the user's own lists, and whether an account is in each of
+    /// them.
+    pub async fn memberships(
+        self,
+        id: &str,
+    ) -> Result<Vec<(List, bool)>, Box<dyn std::error::Error>> {
+        let subject = self.resolve_did(id).await?;
+        let did = self.did()?.to_string();
+        let lists = self.clone().lists(&did).await?;
+        let uris: Vec<String> = lists
+            .iter()
+            .map(|list| list_uri(&list.did, &list.rkey))
+            .collect();
+        // Errors are turned into strings, as the boxed ones can't be held
+        // across threads while waiting for the other lists.
+        let session = &self;
+        let members =
+            join_all(uris.iter().map(|uri| async move {
+                session.list_members(uri).await.map_err(|e| e.to_string())
+            }))
+            .await;
+
+        let mut memberships = Vec::new();
+        for (list, members) in lists.into_iter().zip(members) {
+            let member = members?.iter().any(|i| i.subject.did == subject);
+            memberships.push((list, member));
         }
 
-        Ok(())
+        Ok(memberships)
     }
 
-    pub async fn reply<'a>(
+    /// Returns a starter pack along with its members and feeds.
+    pub async fn starter_pack(
         self,
-        id: &'a str,
-        body: &'a str,
+        did: &str,
+        rkey: &str,
+    ) -> Result<(StarterPack, Vec<Actor>, Vec<Generator>), Box<dyn std::error::Error>> {
+        let saved = saved_feeds(&self.preferences().await?);
+        let pack = self
+            .agent
+            .api
+            .app
+            .bsky
+            .graph
+            .get_starter_pack(Object::from(
+                atrium_api::app::bsky::graph::get_starter_pack::ParametersData {
+                    starter_pack: starter_pack_uri(did, rkey),
+                },
+            ))
+            .await?
+            .data
+            .starter_pack;
+
+        let members = match &pack.list {
+            Some(list) => self
+                .list_members(&list.uri)
+                .await?
+                .iter()
+                .map(|i| Actor::new(&i.subject.handle, &i.subject.display_name))
+                .collect(),
+            None => Vec::new(),
+        };
+        let feeds = pack
+            .feeds
+            .iter()
+            .flatten()
+            .map(|view| Generator::new(view, &saved))
+            .collect();
+
+        Ok((
+            StarterPack::new(
+                &pack.uri,
+                &pack.creator.handle,
+                &pack.record,
+                pack.joined_all_time_count,
+            ),
+            members,
+            feeds,
+        ))
+    }
+
+    pub async fn profile(self, id: &str) -> Result<Profile, Box<dyn std::error::Error>> {
+        let identifier = AtIdentifier::from_str(id)?;
+        let preferences = self.preferences().await?;
+        let account = self
+            .agent
+            .api
+            .app
+            .bsky
+            .actor
+            .get_profile(Object::from(
+                atrium_api::app::bsky::actor::get_profile::ParametersData {
+                    actor: identifier.clone(),
+                },
+            ))
+            .await?;
+        let muted = account
+            .viewer
+            .as_ref()
+            .and_then(|v| v.muted)
+            .unwrap_or(false);
+        let blocking = account
+            .viewer
+            .as_ref()
+            .is_some_and(|v| v.blocking.is_some());
+        let blocked_by = account
+            .viewer
+            .as_ref()
+            .and_then(|v| v.blocked_by)
+            .unwrap_or(false);
+        let following = account
+            .viewer
+            .as_ref()
+            .is_some_and(|v| v.following.is_some());
+        let follows_you = account
+            .viewer
+            .as_ref()
+            .is_some_and(|v| v.followed_by.is_some());
+
+        // Author feeds can't be fetched across blocks.
+        let account_feed = if blocking || blocked_by {
+            Vec::new()
+        } else {
+            self.agent
+                .api
+                .app
+                .bsky
+                .feed
+                .get_author_feed(Object::from(
+                    atrium_api::app::bsky::feed::get_author_feed::ParametersData {
+                        actor: identifier.clone(),
+                        cursor: None,
+                        filter: None,
+                        include_pins: Some(true),
+                        limit: LimitedNonZeroU8::try_from(10).ok(),
+                    },
+                ))
+                .await?
+                .data
+                .feed
+        };
+        // The pinned post comes first in the author feed, and is shown on its
+        // own.
+        let (pinned, account_feed): (Vec<_>, Vec<_>) = account_feed.into_iter().partition(|p| {
+            matches!(
+                p.reason,
+                Some(Union::Refs(FeedViewPostReasonRefs::ReasonPin(_)))
+            )
+        });
+        let moderation = self.moderation(&preferences);
+        let (labels, visibility) = moderation.labels(&account.labels.clone().unwrap_or_default());
+        // Suggestions aren't essential to the page, so failing to get them
+        // shouldn't break it.
+        let suggestions = self
+            .agent
+            .api
+            .app
+            .bsky
+            .graph
+            .get_suggested_follows_by_actor(Object::from(
+                atrium_api::app::bsky::graph::get_suggested_follows_by_actor::ParametersData {
+                    actor: identifier.clone(),
+                },
+            ))
+            .await
+            .map(|s| {
+                s.suggestions
+                    .iter()
+                    .take(5)
+                    .map(Actor::from_profile)
+                    .collect()
+            })
+            .unwrap_or_default();
+        let starter_packs = self
+            .agent
+            .api
+            .app
+            .bsky
+            .graph
+            .get_actor_starter_packs(Object::from(
+                atrium_api::app::bsky::graph::get_actor_starter_packs::ParametersData {
+                    actor: identifier.clone(),
+                    cursor: None,
+                    limit: LimitedNonZeroU8::try_from(10).ok(),
+                },
+            ))
+            .await
+            .map(|s| {
+                s.starter_packs
+                    .iter()
+                    .map(|p| {
+                        StarterPack::new(
+                            &p.uri,
+                            &account.handle,
+                            &p.record,
+                            p.joined_all_time_count,
+                        )
+                    })
+                    .collect()
+            })
+            .unwrap_or_default();
+
+        Ok(Profile {
+            id: account.handle.clone(),
+            name: account
+                .display_name
+                .clone()
+                .unwrap_or(account.handle.to_string()),
+            bio: account.description.clone().unwrap_or("".to_string()),
+            avatar: account.avatar.clone(),
+            banner: account.banner.clone(),
+            followers: account.followers_count.unwrap_or(0) as u64,
+            follows: account.follows_count.unwrap_or(0) as u64,
+            posts_count: account.posts_count.unwrap_or(0) as u64,
+            created: account
+                .created_at
+                .as_ref()
+                .map(|c| c.as_ref().format("%Y-%m-%d").to_string()),
+            following,
+            follows_you,
+            labels,
+            visibility,
+            labeler: account
+                .associated
+                .as_ref()
+                .and_then(|a| a.labeler)
+                .unwrap_or(false),
+            subscribed: moderation::labelers(&preferences).contains(&account.did),
+            muted,
+            blocking,
+            blocked_by,
+            pinned: self.push_feed(&pinned, &moderation).await.pop(),
+            posts: self.push_feed(&account_feed, &moderation).await,
+            suggestions,
+            starter_packs,
+        })
+    }
+
+    pub async fn edit_profile(self, edit: ProfileEdit) -> Result<(), Box<dyn std::error::Error>> {
+        match edit {
+            ProfileEdit::Name(name) => {
+                self.update_profile(|profile| {
+                    profile.display_name = Some(name).filter(|n| !n.is_empty())
+                })
+                .await
+            }
+            ProfileEdit::Bio(bio) => {
+                self.update_profile(|profile| {
+                    profile.description = Some(bio).filter(|b| !b.is_empty())
+                })
+                .await
+            }
+            ProfileEdit::Avatar(image) => {
+                let blob = self.upload_blob(image).await?;
+                self.update_profile(|profile| profile.avatar = Some(blob))
+                    .await
+            }
+            ProfileEdit::Banner(image) => {
+                let blob = self.upload_blob(image).await?;
+                self.update_profile(|profile| profile.banner = Some(blob))
+                    .await
+            }
+        }
+    }
+
+    pub async fn followers(
+        self,
+        id: &str,
+        cursor: Option<String>,
+    ) -> Result<(Vec<Actor>, Option<String>), Box<dyn std::error::Error>> {
+        let followers = self
+            .agent
+            .api
+            .app
+            .bsky
+            .graph
+            .get_followers(Object::from(
+                atrium_api::app::bsky::graph::get_followers::ParametersData {
+                    actor: AtIdentifier::from_str(id)?,
+                    cursor,
+                    limit: LimitedNonZeroU8::try_from(25).ok(),
+                },
+            ))
+            .await?;
+
+        Ok((
+            followers
+                .followers
+                .iter()
+                .map(Actor::from_profile)
+                .collect(),
+            followers.data.cursor,
+        ))
+    }
+
+    pub async fn follows(
+        self,
+        id: &str,
+        cursor: Option<String>,
+    ) -> Result<(Vec<Actor>, Option<String>), Box<dyn std::error::Error>> {
+        let follows = self
+            .agent
+            .api
+            .app
+            .bsky
+            .graph
+            .get_follows(Object::from(
+                atrium_api::app::bsky::graph::get_follows::ParametersData {
+                    actor: AtIdentifier::from_str(id)?,
+                    cursor,
+                    limit: LimitedNonZeroU8::try_from(25).ok(),
+                },
+            ))
+            .await?;
+
+        Ok((
+            follows.follows.iter().map(Actor::from_profile).collect(),
+            follows.data.cursor,
+        ))
+    }
+
+    /// Lists the followers of an account which the user follows too.
+    pub async fn known_followers(
+        self,
+        id: &str,
+        cursor: Option<String>,
+    ) -> Result<(Vec<Actor>, Option<String>), Box<dyn std::error::Error>> {
+        let followers = self
+            .agent
+            .api
+            .app
+            .bsky
+            .graph
+            .get_known_followers(Object::from(
+                atrium_api::app::bsky::graph::get_known_followers::ParametersData {
+                    actor: AtIdentifier::from_str(id)?,
+                    cursor,
+                    limit: LimitedNonZeroU8::try_from(25).ok(),
+                },
+            ))
+            .await?;
+
+        Ok((
+            followers
+                .followers
+                .iter()
+                .map(Actor::from_profile)
+                .collect(),
+            followers.data.cursor,
+        ))
+    }
+
+    pub async fn search(
+        self,
+        query: &Query,
+        sort: &str,
+        cursor: Option<String>,
+    ) -> Result<Search, Box<dyn std::error::Error>> {
+        let moderation = self.moderation(&self.preferences().await?);
+
+        // Accounts are only listed above the first page of posts, and only
+        // for plain queries.
+        let actors = if cursor.is_none() && query.is_plain() {
+            self.agent
+                .api
+                .app
+                .bsky
+                .actor
+                .search_actors(Object::from(
+                    atrium_api::app::bsky::actor::search_actors::ParametersData {
+                        cursor: None,
+                        limit: LimitedNonZeroU8::try_from(5).ok(),
+                        q: Some(query.text.clone()),
+                        term: None,
+                    },
+                ))
+                .await?
+                .data
+                .actors
+                .iter()
+                .map(|a| Actor::new(&a.handle, &a.display_name))
+                .collect()
+        } else {
+            Vec::new()
+        };
+
+        let (posts, cursor) = self.search_posts(query, sort, cursor, &moderation).await?;
+
+        Ok(Search {
+            actors,
+            posts,
+            cursor,
+        })
+    }
+
+    pub async fn tag(
+        self,
+        tag: &str,
+        sort: &str,
+        cursor: Option<String>,
+    ) -> Result<Search, Box<dyn std::error::Error>> {
+        let moderation = self.moderation(&self.preferences().await?);
+        let (posts, cursor) = self
+            .search_posts(&Query::tag(tag), sort, cursor, &moderation)
+            .await?;
+
+        Ok(Search {
+            actors: Vec::new(),
+            posts,
+            cursor,
+        })
+    }
+
+    async fn search_posts(
+        &self,
+        query: &Query,
+        sort: &str,
+        cursor: Option<String>,
+        moderation: &Moderation,
+    ) -> Result<(Vec<Post>, Option<String>), Box<dyn std::error::Error>> {
+        // "me" stands for the signed in account.
+        let account = |a: &Option<String>| -> Result<Option<AtIdentifier>, String> {
+            a.as_deref()
+                .map(|a| match a {
+                    "me" => AtIdentifier::from_str(&self.handle),
+                    _ => AtIdentifier::from_str(a),
+                })
+                .transpose()
+                .map_err(String::from)
+        };
+        let author = account(&query.author)?;
+        let mentions = account(&query.mentions)?;
+        let lang = query
+            .lang
+            .as_deref()
+            .map(Language::from_str)
+            .transpose()
+            .map_err(|_| String::from("invalid language"))?;
+
+        let results = self
+            .agent
+            .api
+            .app
+            .bsky
+            .feed
+            .search_posts(Object::from(
+                atrium_api::app::bsky::feed::search_posts::ParametersData {
+                    author,
+                    cursor,
+                    domain: query.domain.clone(),
+                    lang,
+                    limit: LimitedNonZeroU8::try_from(10).ok(),
+                    mentions,
+                    q: query.text.clone(),
+                    since: query.since.clone(),
+                    sort: Some(sort.to_string()),
+                    tag: (!query.tags.is_empty()).then(|| query.tags.clone()),
+                    until: query.until.clone(),
+                    url: None,
+                },
+            ))
+            .await?;
+
+        Ok((
+            self.push_posts(&results.posts, moderation).await,
+            results.data.cursor,
+        ))
+    }
+
+    /// Turns a feed into posts, leaving out any hidden by moderation.
+    async fn push_feed(
+        &self,
+        feed: &[atrium_api::app::bsky::feed::defs::FeedViewPost],
+        moderation: &Moderation,
+    ) -> Vec<Post> {
+        join_all(
+            feed.iter()
+                .map(|p| async { Post::push(p, &self.objects, moderation).await }),
+        )
+        .await
+        .into_iter()
+        .filter(|p| p.visibility != Visibility::Hide)
+        .collect()
+    }
+
+    /// Same as [`Session::push_feed`], for posts which don't come from a
+    /// feed, such as search results.
+    async fn push_posts(
+        &self,
+        posts: &[atrium_api::app::bsky::feed::defs::PostView],
+        moderation: &Moderation,
+    ) -> Vec<Post> {
+        let feed: Vec<_> = posts
+            .iter()
+            .map(|p| {
+                Object::from(atrium_api::app::bsky::feed::defs::FeedViewPostData {
+                    feed_context: None,
+                    post: p.clone(),
+                    reason: None,
+                    reply: None,
+                })
+            })
+            .collect();
+
+        self.push_feed(&feed, moderation).await
+    }
+
+    /// Fetches a single post, clicking through its content warning if it
+    /// has one. Posts hidden by the user's moderation settings stay hidden.
+    pub async fn view(self, id: &str) -> Result<Post, Box<dyn std::error::Error>> {
+        let uri = self.object(id).await?.uri;
+        let moderation = self.moderation(&self.preferences().await?);
+        let mut post = self.view_uri(uri, &moderation).await?;
+        match post.visibility {
+            Visibility::Hide => return Err(Box::from("post not found")),
+            Visibility::Warn => post.visibility = Visibility::Show,
+            Visibility::Show => (),
+        }
+
+        Ok(post)
+    }
+
+    /// Gets a post by its author and record key, which stay the same across
+    /// restarts unlike its ID. Unlike [`Session::view`], content warnings
+    /// are kept.
+    pub async fn permalink(self, id: &str, rkey: &str) -> Result<Post, Box<dyn std::error::Error>> {
+        let uri = self.post_uri(id, rkey).await?;
+        let moderation = self.moderation(&self.preferences().await?);
+        let post = self.view_uri(uri, &moderation).await?;
+        if post.visibility == Visibility::Hide {
+            return Err(Box::from("post not found"));
+        }
+
+        Ok(post)
+    }
+
+    /// Returns the permalink of a post seen earlier, where it's shown along
+    /// with who liked, reposted and quoted it.
+    pub async fn post_path(self, id: &str) -> Result<String, Box<dyn std::error::Error>> {
+        let object = self.object(id).await?;
+        let (did, rkey) = at_uri(&object.uri).ok_or("invalid post URI")?;
+
+        Ok(format!("/@{did}/p/{rkey}"))
+    }
+
+    /// Builds the AT URI of a post from its author and record key.
+    async fn post_uri(&self, id: &str, rkey: &str) -> Result<String, Box<dyn std::error::Error>> {
+        let did = self.resolve_did(id).await?;
+
+        Ok(format!(
+            "at://{}/{}/{}",
+            did.as_str(),
+            atrium_api::app::bsky::feed::Post::NSID,
+            rkey
+        ))
+    }
+
+    async fn view_uri(
+        &self,
+        uri: String,
+        moderation: &Moderation,
+    ) -> Result<Post, Box<dyn std::error::Error>> {
+        let posts = self
+            .agent
+            .api
+            .app
+            .bsky
+            .feed
+            .get_posts(Object::from(
+                atrium_api::app::bsky::feed::get_posts::ParametersData { uris: vec![uri] },
+            ))
+            .await?;
+        let post = posts.posts.first().ok_or("post not found")?;
+
+        Ok(Post::push(
+            &Object::from(atrium_api::app::bsky::feed::defs::FeedViewPostData {
+                feed_context: None,
+                post: post.clone(),
+                reason: None,
+                reply: None,
+            }),
+            &self.objects,
+            moderation,
+        )
+        .await)
+    }
+
+    pub async fn likes(
+        self,
+        id: &str,
+        rkey: &str,
+        cursor: Option<String>,
+    ) -> Result<(Vec<Actor>, Option<String>), Box<dyn std::error::Error>> {
+        let uri = self.post_uri(id, rkey).await?;
+        let likes = self
+            .agent
+            .api
+            .app
+            .bsky
+            .feed
+            .get_likes(Object::from(
+                atrium_api::app::bsky::feed::get_likes::ParametersData {
+                    cid: None,
+                    cursor,
+                    limit: LimitedNonZeroU8::try_from(25).ok(),
+                    uri,
+                },
+            ))
+            .await?;
+
+        Ok((
+            likes
+                .likes
+                .iter()
+                .map(|l| Actor::from_profile(&l.actor))
+                .collect(),
+            likes.data.cursor,
+        ))
+    }
+
+    pub async fn reposted_by(
+        self,
+        id: &str,
+        rkey: &str,
+        cursor: Option<String>,
+    ) -> Result<(Vec<Actor>, Option<String>), Box<dyn std::error::Error>> {
+        let uri = self.post_uri(id, rkey).await?;
+        let reposts = self
+            .agent
+            .api
+            .app
+            .bsky
+            .feed
+            .get_reposted_by(Object::from(
+                atrium_api::app::bsky::feed::get_reposted_by::ParametersData {
+                    cid: None,
+                    cursor,
+                    limit: LimitedNonZeroU8::try_from(25).ok(),
+                    uri,
+                },
+            ))
+            .await?;
+
+        Ok((
+            reposts
+                .reposted_by
+                .iter()
+                .map(Actor::from_profile)
+                .collect(),
+            reposts.data.cursor,
+        ))
+    }
+
+    pub async fn quotes(
+        self,
+        id: &str,
+        rkey: &str,
+        cursor: Option<String>,
+    ) -> Result<(Vec<Post>, Option<String>), Box<dyn std::error::Error>> {
+        let uri = self.post_uri(id, rkey).await?;
+        let moderation = self.moderation(&self.preferences().await?);
+        let quotes = self
+            .agent
+            .api
+            .app
+            .bsky
+            .feed
+            .get_quotes(Object::from(
+                atrium_api::app::bsky::feed::get_quotes::ParametersData {
+                    cid: None,
+                    cursor,
+                    limit: LimitedNonZeroU8::try_from(10).ok(),
+                    uri,
+                },
+            ))
+            .await?;
+
+        Ok((
+            self.push_posts(&quotes.posts, &moderation).await,
+            quotes.data.cursor,
+        ))
+    }
+
+    pub async fn follow(self, id: &str) -> Result<(), Box<dyn std::error::Error>> {
+        let identifier = AtIdentifier::from_str(id)?;
+        let account = self
+            .agent
+            .api
+            .app
+            .bsky
+            .actor
+            .get_profile(Object::from(
+                atrium_api::app::bsky::actor::get_profile::ParametersData {
+                    actor: identifier.clone(),
+                },
+            ))
+            .await?;
+        let following = account.viewer.as_ref().and_then(|v| v.following.clone());
+
+        if let Some(uri) = following {
+            self.agent
+                .api
+                .com
+                .atproto
+                .repo
+                .delete_record(Object::from(
+                    atrium_api::com::atproto::repo::delete_record::InputData {
+                        collection: Nsid::from_str(atrium_api::app::bsky::graph::Follow::NSID)?,
+                        repo: self.id.clone(),
+                        rkey: uri.split("/").last().unwrap().to_string(),
+                        swap_commit: None,
+                        swap_record: None,
+                    },
+                ))
+                .await?;
+        } else {
+            self.agent
+                .api
+                .com
+                .atproto
+                .repo
+                .create_record(Object::from(
+                    atrium_api::com::atproto::repo::create_record::InputData {
+                        collection: Nsid::from_str(atrium_api::app::bsky::graph::Follow::NSID)?,
+                        record: atrium_api::app::bsky::graph::follow::RecordData {
+                            created_at: Datetime::now(),
+                            subject: account.did.clone(),
+                        }
+                        .try_into_unknown()?,
+                        repo: self.id.clone(),
+                        rkey: None,
+                        swap_commit: None,
+                        validate: None,
+                    },
+                ))
+                .await?;
+        }
+
+        Ok(())
+    }
+
+    pub async fn mute(self, id: &str) -> Result<(), Box<dyn std::error::Error>> {
+        let identifier = AtIdentifier::from_str(id)?;
+        let account = self
+            .agent
+            .api
+            .app
+            .bsky
+            .actor
+            .get_profile(Object::from(
+                atrium_api::app::bsky::actor::get_profile::ParametersData {
+                    actor: identifier.clone(),
+                },
+            ))
+            .await?;
+
+        if account
+            .viewer
+            .as_ref()
+            .and_then(|v| v.muted)
+            .unwrap_or(false)
+        {
+            self.agent
+                .api
+                .app
+                .bsky
+                .graph
+                .unmute_actor(Object::from(
+                    atrium_api::app::bsky::graph::unmute_actor::InputData { actor: identifier },
+                ))
+                .await?;
+        } else {
+            self.agent
+                .api
+                .app
+                .bsky
+                .graph
+                .mute_actor(Object::from(
+                    atrium_api::app::bsky::graph::mute_actor::InputData { actor: identifier },
+                ))
+                .await?;
+        }
+
+        Ok(())
+    }
+
+    pub async fn block(self, id: &str) -> Result<(), Box<dyn std::error::Error>> {
+        let identifier = AtIdentifier::from_str(id)?;
+        let account = self
+            .agent
+            .api
+            .app
+            .bsky
+            .actor
+            .get_profile(Object::from(
+                atrium_api::app::bsky::actor::get_profile::ParametersData {
+                    actor: identifier.clone(),
+                },
+            ))
+            .await?;
+
+        if let Some(uri) = account.viewer.as_ref().and_then(|v| v.blocking.clone()) {
+            self.agent
+                .api
+                .com
+                .atproto
+                .repo
+                .delete_record(Object::from(
+                    atrium_api::com::atproto::repo::delete_record::InputData {
+                        collection: Nsid::from_str(atrium_api::app::bsky::graph::Block::NSID)?,
+                        repo: self.id.clone(),
+                        rkey: uri.split('/').next_back().unwrap().to_string(),
+                        swap_commit: None,
+                        swap_record: None,
+                    },
+                ))
+                .await?;
+        } else {
+            self.agent
+                .api
+                .com
+                .atproto
+                .repo
+                .create_record(Object::from(
+                    atrium_api::com::atproto::repo::create_record::InputData {
+                        collection: Nsid::from_str(atrium_api::app::bsky::graph::Block::NSID)?,
+                        record: KnownRecord::AppBskyGraphBlock(Box::new(Object::from(
+                            atrium_api::app::bsky::graph::block::RecordData {
+                                created_at: Datetime::now(),
+                                subject: account.did.clone(),
+                            },
+                        )))
+                        .try_into_unknown()?,
+                        repo: self.id.clone(),
+                        rkey: None,
+                        swap_commit: None,
+                        validate: None,
+                    },
+                ))
+                .await?;
+        }
+
+        Ok(())
+    }
+
+    pub async fn report_post(
+        self,
+        id: &str,
+        reason_type: &str,
+        comment: Option<&str>,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        let object = self.object(id).await?;
+
+        self.report(
+            atrium_api::com::atproto::moderation::create_report::InputSubjectRefs::ComAtprotoRepoStrongRefMain(
+                Box::new(Object::from(object)),
+            ),
+            reason_type,
+            comment,
+        )
+        .await
+    }
+
+    pub async fn report_account(
+        self,
+        id: &str,
+        reason_type: &str,
+        comment: Option<&str>,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        let identifier = AtIdentifier::from_str(id)?;
+        let account = self
+            .agent
+            .api
+            .app
+            .bsky
+            .actor
+            .get_profile(Object::from(
+                atrium_api::app::bsky::actor::get_profile::ParametersData { actor: identifier },
+            ))
+            .await?;
+
+        self.report(
+            atrium_api::com::atproto::moderation::create_report::InputSubjectRefs::ComAtprotoAdminDefsRepoRef(
+                Box::new(Object::from(atrium_api::com::atproto::admin::defs::RepoRefData {
+                    did: account.did.clone(),
+                })),
+            ),
+            reason_type,
+            comment,
+        )
+        .await
+    }
+
+    async fn report(
+        &self,
+        subject: atrium_api::com::atproto::moderation::create_report::InputSubjectRefs,
+        reason_type: &str,
+        comment: Option<&str>,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        self.agent
+            .api
+            .com
+            .atproto
+            .moderation
+            .create_report(Object::from(
+                atrium_api::com::atproto::moderation::create_report::InputData {
+                    reason: comment.map(String::from),
+                    reason_type: reason_type.to_string(),
+                    subject: Union::Refs(subject),
+                },
+            ))
+            .await?;
+
+        Ok(())
+    }
+
+    pub async fn muted(self) -> Result<Vec<Actor>, Box<dyn std::error::Error>> {
+        let mutes = self
+            .agent
+            .api
+            .app
+            .bsky
+            .graph
+            .get_mutes(Object::from(
+                atrium_api::app::bsky::graph::get_mutes::ParametersData {
+                    cursor: None,
+                    limit: LimitedNonZeroU8::try_from(100).ok(),
+                },
+            ))
+            .await?;
+
+        Ok(mutes
+            .mutes
+            .iter()
+            .map(|p| Actor::new(&p.handle, &p.display_name))
+            .collect())
+    }
+
+    pub async fn blocked(self) -> Result<Vec<Actor>, Box<dyn std::error::Error>> {
+        let blocks = self
+            .agent
+            .api
+            .app
+            .bsky
+            .graph
+            .get_blocks(Object::from(
+                atrium_api::app::bsky::graph::get_blocks::ParametersData {
+                    cursor: None,
+                    limit: LimitedNonZeroU8::try_from(100).ok(),
+                },
+            ))
+            .await?;
+
+        Ok(blocks
+            .blocks
+            .iter()
+            .map(|p| Actor::new(&p.handle, &p.display_name))
+            .collect())
+    }
+
+    pub async fn like(self, id: &str) -> Result<(), Box<dyn std::error::Error>> {
+        let hash_map = self.objects.lock().await;
+        let object = hash_map.get(id).unwrap();
+        let post = self
+            .agent
+            .api
+            .app
+            .bsky
+            .feed
+            .get_posts(Object::from(
+                atrium_api::app::bsky::feed::get_posts::ParametersData {
+                    uris: vec![object.uri.clone()],
+                },
+            ))
+            .await?;
+
+        if let Some(like) = post
+            .clone()
+            .posts
+            .first()
+            .unwrap()
+            .viewer
+            .clone()
+            .unwrap()
+            .like
+            .clone()
+        {
+            self.agent
+                .api
+                .com
+                .atproto
+                .repo
+                .delete_record(Object::from(
+                    atrium_api::com::atproto::repo::delete_record::InputData {
+                        collection: Nsid::from_str(atrium_api::app::bsky::feed::Like::NSID)?,
+                        repo: self.id.clone(),
+                        rkey: like.split('/').next_back().unwrap().to_string(),
+                        swap_commit: None,
+                        swap_record: None,
+                    },
+                ))
+                .await?;
+        } else {
+            self.agent
+                .api
+                .com
+                .atproto
+                .repo
+                .create_record(Object::from(
+                    atrium_api::com::atproto::repo::create_record::InputData {
+                        collection: Nsid::from_str(atrium_api::app::bsky::feed::Like::NSID)?,
+                        record: atrium_api::record::KnownRecord::AppBskyFeedLike(Box::new(
+                            Object::from(atrium_api::app::bsky::feed::like::RecordData {
+                                created_at: atrium_api::types::string::Datetime::now(),
+                                subject: Object::from(object.clone()),
+                            }),
+                        ))
+                        .try_into_unknown()?,
+                        repo: self.id.clone(),
+                        rkey: None,
+                        swap_commit: None,
+                        validate: None,
+                    },
+                ))
+                .await?;
+        }
+
+        Ok(())
+    }
+
+    pub async fn repost(self, id: &str) -> Result<(), Box<dyn std::error::Error>> {
+        let hash_map = self.objects.lock().await;
+        let object = hash_map.get(id).unwrap();
+        let post = self
+            .agent
+            .api
+            .app
+            .bsky
+            .feed
+            .get_posts(Object::from(
+                atrium_api::app::bsky::feed::get_posts::ParametersData {
+                    uris: vec![object.uri.clone()],
+                },
+            ))
+            .await?;
+
+        if let Some(repost) = post
+            .clone()
+            .posts
+            .first()
+            .unwrap()
+            .viewer
+            .clone()
+            .unwrap()
+            .repost
+            .clone()
+        {
+            self.agent
+                .api
+                .com
+                .atproto
+                .repo
+                .delete_record(Object::from(
+                    atrium_api::com::atproto::repo::delete_record::InputData {
+                        collection: Nsid::from_str(atrium_api::app::bsky::feed::Like::NSID)?,
+                        repo: self.id.clone(),
+                        rkey: repost.split('/').next_back().unwrap().to_string(),
+                        swap_commit: None,
+                        swap_record: None,
+                    },
+                ))
+                .await?;
+        } else {
+            self.agent
+                .api
+                .com
+                .atproto
+                .repo
+                .create_record(Object::from(
+                    atrium_api::com::atproto::repo::create_record::InputData {
+                        collection: Nsid::from_str(atrium_api::app::bsky::feed::Repost::NSID)?,
+                        record: atrium_api::record::KnownRecord::AppBskyFeedRepost(Box::new(
+                            Object::from(atrium_api::app::bsky::feed::repost::RecordData {
+                                created_at: atrium_api::types::string::Datetime::now(),
+                                subject: Object::from(object.clone()),
+                            }),
+                        ))
+                        .try_into_unknown()?,
+                        repo: self.id.clone(),
+                        rkey: None,
+                        swap_commit: None,
+                        validate: None,
+                    },
+                ))
+                .await?;
+        }
+
+        Ok(())
+    }
+
+    /// Mutes the thread a post belongs to, or unmutes it if already muted.
+    pub async fn mute_thread(self, id: &str) -> Result<(), Box<dyn std::error::Error>> {
+        let object = self.object(id).await?;
+        let post = self
+            .agent
+            .api
+            .app
+            .bsky
+            .feed
+            .get_posts(Object::from(
+                atrium_api::app::bsky::feed::get_posts::ParametersData {
+                    uris: vec![object.uri.clone()],
+                },
+            ))
+            .await?;
+        let post = post.posts.first().ok_or("post not found")?;
+
+        // Threads are muted through their root post.
+        let root = match KnownRecord::try_from_unknown(post.record.clone())? {
+            KnownRecord::AppBskyFeedPost(record) => record
+                .reply
+                .as_ref()
+                .map_or(object.uri.clone(), |r| r.root.uri.clone()),
+            _ => object.uri.clone(),
+        };
+
+        if post
+            .viewer
+            .as_ref()
+            .and_then(|v| v.thread_muted)
+            .unwrap_or(false)
+        {
+            self.agent
+                .api
+                .app
+                .bsky
+                .graph
+                .unmute_thread(Object::from(
+                    atrium_api::app::bsky::graph::unmute_thread::InputData { root },
+                ))
+                .await?;
+        } else {
+            self.agent
+                .api
+                .app
+                .bsky
+                .graph
+                .mute_thread(Object::from(
+                    atrium_api::app::bsky::graph::mute_thread::InputData { root },
+                ))
+                .await?;
+        }
+
+        Ok(())
+    }
+
+    /// Pins one of the user's posts to their profile, or unpins it if it's
+    /// already pinned.
+    pub async fn pin(self, id: &str) -> Result<(), Box<dyn std::error::Error>> {
+        let object = self.object(id).await?;
+        let did = self.did()?.to_string();
+        if at_uri(&object.uri).is_none_or(|(authority, _)| authority != did) {
+            return Err(Box::from("only your own posts can be pinned"));
+        }
+
+        self.update_profile(|profile| {
+            profile.pinned_post = match profile.pinned_post.take() {
+                Some(pinned) if pinned.uri == object.uri => None,
+                _ => Some(Object::from(object)),
+            }
+        })
+        .await
+    }
+
+    pub async fn reply<'a>(
+        self,
+        id: &'a str,
+        body: &'a str,
+        options: &PostOptions,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        let hash_map = self.objects.lock().await;
+        let object = hash_map.get(id).unwrap();
+        let post = self
+            .agent
+            .api
+            .com
+            .atproto
+            .repo
+            .get_record(Object::from(
+                atrium_api::com::atproto::repo::get_record::ParametersData {
+                    cid: Some(object.cid.clone()),
+                    collection: Nsid::from_str(atrium_api::app::bsky::feed::Post::NSID)?,
+                    repo: AtIdentifier::Did(Did::from_str(object.uri.split("/").nth(2).unwrap())?),
+                    rkey: object.uri.split("/").last().unwrap().to_string(),
+                },
+            ))
+            .await?;
+
+        let langs = self.post_langs(body, options).await?;
+        let reply = self
+            .agent
+            .api
+            .com
+            .atproto
+            .repo
+            .create_record(Object::from(
+                atrium_api::com::atproto::repo::create_record::InputData {
+                    collection: Nsid::from_str(atrium_api::app::bsky::feed::Post::NSID)?,
+                    record: atrium_api::record::KnownRecord::AppBskyFeedPost(Box::from(
+                        Object::from(atrium_api::app::bsky::feed::post::RecordData {
+                            created_at: Datetime::now(),
+                            embed: None,
+                            entities: None,
+                            facets: None,
+                            labels: self_labels(options),
+                            langs,
+                            reply: Some(Object::from(
+                                atrium_api::app::bsky::feed::post::ReplyRefData {
+                                    parent: Object::from(
+                                        atrium_api::com::atproto::repo::strong_ref::MainData {
+                                            cid: object.cid.clone(),
+                                            uri: object.uri.clone(),
+                                        },
+                                    ),
+                                    root: {
+                                        if let KnownRecord::AppBskyFeedPost(post_boxed) =
+                                            KnownRecord::try_from_unknown(post.value.clone())?
+                                        {
+                                            if let Some(reply) = Box::leak(post_boxed).reply.clone() {
+                                                reply
+                                                .root
+                                                .clone()
+                                            } else {
+                                                Object::from(atrium_api::com::atproto::repo::strong_ref::MainData {
+                                                    cid: object.cid.clone(),
+                                                    uri: object.uri.clone(),
+                                                })
+                                            }
+                                        } else {
+                                            return Err(Box::from("root not found"));
+                                        }
+                                    },
+                                },
+                            )),
+                            tags: None,
+                            text: body.to_string(),
+                        }),
+                    ))
+                    .try_into_unknown()?,
+                    repo: self.id.clone(),
+                    rkey: None,
+                    swap_commit: None,
+                    validate: None,
+                },
+            ))
+            .await?;
+
+        // Threadgates only apply to the root of a thread, so replies just
+        // carry the quote controls.
+        if !options.quotes {
+            self.apply_gates(
+                &reply.uri,
+                &PostOptions {
+                    replies: None,
+                    ..options.clone()
+                },
+            )
+            .await?;
+        }
+
+        Ok(())
+    }
+
+    pub async fn post(
+        self,
+        body: &str,
+        options: &PostOptions,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        let langs = self.post_langs(body, options).await?;
+        let post = self
+            .agent
+            .api
+            .com
+            .atproto
+            .repo
+            .create_record(Object::from(
+                atrium_api::com::atproto::repo::create_record::InputData {
+                    collection: Nsid::from_str(atrium_api::app::bsky::feed::Post::NSID)?,
+                    record: atrium_api::record::KnownRecord::AppBskyFeedPost(Box::from(
+                        Object::from(atrium_api::app::bsky::feed::post::RecordData {
+                            created_at: Datetime::now(),
+                            embed: None,
+                            entities: None,
+                            facets: None,
+                            labels: self_labels(options),
+                            langs,
+                            reply: None,
+                            tags: None,
+                            text: body.to_string(),
+                        }),
+                    ))
+                    .try_into_unknown()?,
+                    repo: self.id.clone(),
+                    rkey: None,
+                    swap_commit: None,
+                    validate: None,
+                },
+            ))
+            .await?;
+
+        if options.replies.is_some() || !options.quotes {
+            self.apply_gates(&post.uri, options).await?;
+        }
+
+        Ok(())
+    }
+
+    pub async fn gate(
+        self,
+        id: &str,
+        options: &PostOptions,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        let object = self.object(id).await?;
+
+        if object.uri.split('/').nth(2) != Some(self.did()?.as_str()) {
+            return Err(Box::from("only your own posts can be gated"));
+        }
+
+        self.apply_gates(&object.uri, options).await
+    }
+
+    /// Writes the threadgate and postgate records for a post, or removes
+    /// them when the options allow everyone to reply or quote.
+    async fn apply_gates(
+        &self,
+        uri: &str,
+        options: &PostOptions,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        let rkey = uri.split('/').next_back().unwrap().to_string();
+
+        if let Some(rules) = &options.replies {
+            let mut allow = Vec::new();
+            for rule in rules {
+                allow.push(match rule {
+                    ReplyRule::Mentioned => Union::Refs(
+                        atrium_api::app::bsky::feed::threadgate::RecordAllowItem::MentionRule(
+                            Box::new(Object::from(
+                                atrium_api::app::bsky::feed::threadgate::MentionRuleData {},
+                            )),
+                        ),
+                    ),
+                    ReplyRule::Following => Union::Refs(
+                        atrium_api::app::bsky::feed::threadgate::RecordAllowItem::FollowingRule(
+                            Box::new(Object::from(
+                                atrium_api::app::bsky::feed::threadgate::FollowingRuleData {},
+                            )),
+                        ),
+                    ),
+                    // The follower rule isn't part of the lexicon shipped with
+                    // ATrium yet, so it has to be built by hand.
+                    ReplyRule::Followers => Union::Unknown(UnknownData {
+                        r#type: String::from("app.bsky.feed.threadgate#followerRule"),
+                        data: Ipld::Map(BTreeMap::new()),
+                    }),
+                    ReplyRule::List(list) => Union::Refs(
+                        atrium_api::app::bsky::feed::threadgate::RecordAllowItem::ListRule(
+                            Box::new(Object::from(
+                                atrium_api::app::bsky::feed::threadgate::ListRuleData {
+                                    list: format!(
+                                        "at://{}/{}/{}",
+                                        self.did()?.as_str(),
+                                        atrium_api::app::bsky::graph::List::NSID,
+                                        list
+                                    ),
+                                },
+                            )),
+                        ),
+                    ),
+                });
+            }
+
+            self.agent
+                .api
+                .com
+                .atproto
+                .repo
+                .put_record(Object::from(
+                    atrium_api::com::atproto::repo::put_record::InputData {
+                        collection: Nsid::from_str(atrium_api::app::bsky::feed::Threadgate::NSID)?,
+                        record: KnownRecord::AppBskyFeedThreadgate(Box::new(Object::from(
+                            atrium_api::app::bsky::feed::threadgate::RecordData {
+                                allow: Some(allow),
+                                created_at: Datetime::now(),
+                                hidden_replies: None,
+                                post: uri.to_string(),
+                            },
+                        )))
+                        .try_into_unknown()?,
+                        repo: self.id.clone(),
+                        rkey: rkey.clone(),
+                        swap_commit: None,
+                        swap_record: None,
+                        validate: None,
+                    },
+                ))
+                .await?;
+        } else {
+            self.agent
+                .api
+                .com
+                .atproto
+                .repo
+                .delete_record(Object::from(
+                    atrium_api::com::atproto::repo::delete_record::InputData {
+                        collection: Nsid::from_str(atrium_api::app::bsky::feed::Threadgate::NSID)?,
+                        repo: self.id.clone(),
+                        rkey: rkey.clone(),
+                        swap_commit: None,
+                        swap_record: None,
+                    },
+                ))
+                .await?;
+        }
+
+        if options.quotes {
+            self.agent
+                .api
+                .com
+                .atproto
+                .repo
+                .delete_record(Object::from(
+                    atrium_api::com::atproto::repo::delete_record::InputData {
+                        collection: Nsid::from_str(atrium_api::app::bsky::feed::Postgate::NSID)?,
+                        repo: self.id.clone(),
+                        rkey,
+                        swap_commit: None,
+                        swap_record: None,
+                    },
+                ))
+                .await?;
+        } else {
+            self.agent
+                .api
+                .com
+                .atproto
+                .repo
+                .put_record(Object::from(
+                    atrium_api::com::atproto::repo::put_record::InputData {
+                        collection: Nsid::from_str(atrium_api::app::bsky::feed::Postgate::NSID)?,
+                        record: KnownRecord::AppBskyFeedPostgate(Box::new(Object::from(
+                            atrium_api::app::bsky::feed::postgate::RecordData {
+                                created_at: Datetime::now(),
+                                detached_embedding_uris: None,
+                                embedding_rules: Some(vec![Union::Refs(
+                                    atrium_api::app::bsky::feed::postgate::RecordEmbeddingRulesItem::DisableRule(
+                                        Box::new(Object::from(
+                                            atrium_api::app::bsky::feed::postgate::DisableRuleData {},
+                                        )),
+                                    ),
+                                )]),
+                                post: uri.to_string(),
+                            },
+                        )))
+                        .try_into_unknown()?,
+                        repo: self.id.clone(),
+                        rkey,
+                        swap_commit: None,
+                        swap_record: None,
+                        validate: None,
+                    },
+                ))
+                .await?;
+        }
+
+        Ok(())
+    }
+
+    pub async fn labelers(self) -> Result<Vec<Actor>, Box<dyn std::error::Error>> {
+        let dids = moderation::labelers(&self.preferences().await?);
+        if dids.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let services = self
+            .agent
+            .api
+            .app
+            .bsky
+            .labeler
+            .get_services(Object::from(
+                atrium_api::app::bsky::labeler::get_services::ParametersData {
+                    detailed: None,
+                    dids,
+                },
+            ))
+            .await?;
+
+        Ok(services
+            .views
+            .iter()
+            .filter_map(|v| match v {
+                Union::Refs(OutputViewsItem::AppBskyLabelerDefsLabelerView(view)) => {
+                    Some(view.creator.clone())
+                }
+                Union::Refs(OutputViewsItem::AppBskyLabelerDefsLabelerViewDetailed(view)) => {
+                    Some(view.creator.clone())
+                }
+                Union::Unknown(_) => None,
+            })
+            .map(|creator| Actor::new(&creator.handle, &creator.display_name))
+            .collect())
+    }
+
+    /// Subscribes to a labeler, or unsubscribes if already subscribed.
+    pub async fn subscribe(self, id: &str) -> Result<(), Box<dyn std::error::Error>> {
+        let identifier = AtIdentifier::from_str(id)?;
+        let account = self
+            .agent
+            .api
+            .app
+            .bsky
+            .actor
+            .get_profile(Object::from(
+                atrium_api::app::bsky::actor::get_profile::ParametersData { actor: identifier },
+            ))
+            .await?;
+        let mut preferences = self.preferences().await?;
+
+        let mut labelers = moderation::labelers(&preferences);
+        if let Some(position) = labelers.iter().position(|did| did == &account.did) {
+            labelers.remove(position);
+        } else {
+            labelers.push(account.did.clone());
+        }
+
+        preferences.retain(|p| !matches!(p, Union::Refs(PreferencesItem::LabelersPref(_))));
+        preferences.push(Union::Refs(PreferencesItem::LabelersPref(Box::new(
+            Object::from(atrium_api::app::bsky::actor::defs::LabelersPrefData {
+                labelers: labelers
+                    .iter()
+                    .map(|did| {
+                        Object::from(atrium_api::app::bsky::actor::defs::LabelerPrefItemData {
+                            did: did.clone(),
+                        })
+                    })
+                    .collect(),
+            }),
+        ))));
+
+        self.put_preferences(preferences).await
+    }
+
+    /// Saves a feed generator, or removes it from the saved feeds if it's
+    /// already there.
+    pub async fn save_feed(self, did: &str, rkey: &str) -> Result<(), Box<dyn std::error::Error>> {
+        let uri = generator_uri(did, rkey);
+        let mut preferences = self.preferences().await?;
+        let mut saved = saved_feeds(&preferences);
+
+        if let Some(position) = saved.iter().position(|s| s.value == uri) {
+            saved.remove(position);
+        } else {
+            saved.push(saved_feed(uri, false));
+        }
+
+        replace_saved_feeds(&mut preferences, saved);
+        self.put_preferences(preferences).await
+    }
+
+    /// Pins a feed generator to the home page, saving it if needed, or unpins
+    /// it.
+    pub async fn pin_feed(self, did: &str, rkey: &str) -> Result<(), Box<dyn std::error::Error>> {
+        let uri = generator_uri(did, rkey);
+        let mut preferences = self.preferences().await?;
+        let mut saved = saved_feeds(&preferences);
+
+        if let Some(item) = saved.iter_mut().find(|s| s.value == uri) {
+            item.pinned = !item.pinned;
+        } else {
+            saved.push(saved_feed(uri, true));
+        }
+
+        replace_saved_feeds(&mut preferences, saved);
+        self.put_preferences(preferences).await
+    }
+
+    pub async fn like_feed(self, did: &str, rkey: &str) -> Result<(), Box<dyn std::error::Error>> {
+        let generator = self
+            .agent
+            .api
+            .app
+            .bsky
+            .feed
+            .get_feed_generator(Object::from(
+                atrium_api::app::bsky::feed::get_feed_generator::ParametersData {
+                    feed: generator_uri(did, rkey),
+                },
+            ))
+            .await?;
+
+        if let Some(like) = generator.view.viewer.as_ref().and_then(|v| v.like.clone()) {
+            self.agent
+                .api
+                .com
+                .atproto
+                .repo
+                .delete_record(Object::from(
+                    atrium_api::com::atproto::repo::delete_record::InputData {
+                        collection: Nsid::from_str(atrium_api::app::bsky::feed::Like::NSID)?,
+                        repo: self.id.clone(),
+                        rkey: like.split('/').next_back().unwrap().to_string(),
+                        swap_commit: None,
+                        swap_record: None,
+                    },
+                ))
+                .await?;
+        } else {
+            self.agent
+                .api
+                .com
+                .atproto
+                .repo
+                .create_record(Object::from(
+                    atrium_api::com::atproto::repo::create_record::InputData {
+                        collection: Nsid::from_str(atrium_api::app::bsky::feed::Like::NSID)?,
+                        record: atrium_api::record::KnownRecord::AppBskyFeedLike(Box::new(
+                            Object::from(atrium_api::app::bsky::feed::like::RecordData {
+                                created_at: Datetime::now(),
+                                subject: Object::from(MainData {
+                                    cid: generator.view.cid.clone(),
+                                    uri: generator.view.uri.clone(),
+                                }),
+                            }),
+                        ))
+                        .try_into_unknown()?,
+                        repo: self.id.clone(),
+                        rkey: None,
+                        swap_commit: None,
+                        validate: None,
+                    },
+                ))
+                .await?;
+        }
+
+        Ok(())
+    }
+
+    /// Creates a list, returning its DID and record key.
+    pub async fn create_list(
+        self,
+        name: &str,
+        description: Option<&str>,
+        purpose: ListPurpose,
+    ) -> Result<(String, String), Box<dyn std::error::Error>> {
+        let record = self
+            .agent
+            .api
+            .com
+            .atproto
+            .repo
+            .create_record(Object::from(
+                atrium_api::com::atproto::repo::create_record::InputData {
+                    collection: Nsid::from_str(atrium_api::app::bsky::graph::List::NSID)?,
+                    record: KnownRecord::AppBskyGraphList(Box::new(Object::from(
+                        atrium_api::app::bsky::graph::list::RecordData {
+                            avatar: None,
+                            created_at: Datetime::now(),
+                            description: description.map(String::from),
+                            description_facets: None,
+                            labels: None,
+                            name: name.to_string(),
+                            purpose: purpose.as_ref().to_string(),
+                        },
+                    )))
+                    .try_into_unknown()?,
+                    repo: self.id.clone(),
+                    rkey: None,
+                    swap_commit: None,
+                    validate: None,
+                },
+            ))
+            .await?;
+
+        Ok(at_uri(&record.uri).ok_or("invalid record URI")?)
+    }
+
+    /// Deletes one of the user's lists along with its items.
+    pub async fn delete_list(
+        self,
+        did: &str,
+        rkey: &str,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        if did != self.did()?.as_str() {
+            return Err(Box::from("only your own lists can be deleted"));
+        }
+        let uri = list_uri(did, rkey);
+
+        // Items are looked up in the repository rather than through the
+        // AppView, which leaves out accounts it no longer shows.
+        let items = self.list_items().await?;
+        for (item, _, _) in items.iter().filter(|(_, list, _)| list == &uri) {
+            self.delete_record(atrium_api::app::bsky::graph::Listitem::NSID, item)
+                .await?;
+        }
+
+        self.delete_record(atrium_api::app::bsky::graph::List::NSID, rkey)
+            .await
+    }
+
+    /// Adds an account to one of the user's lists, or removes it if it's
+    /// already there.
+    pub async fn toggle_member(
+        self,
+        id: &str,
+        rkey: &str,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        let subject = self.resolve_did(id).await?;
+        let did = self.did()?.to_string();
+        let uri = list_uri(&did, rkey);
+
+        let members = self.list_members(&uri).await?;
+        if let Some(item) = members.iter().find(|i| i.subject.did == subject) {
+            let (_, item) = at_uri(&item.uri).ok_or("invalid record URI")?;
+            return self
+                .delete_record(atrium_api::app::bsky::graph::Listitem::NSID, &item)
+                .await;
+        }
+
+        self.agent
+            .api
+            .com
+            .atproto
+            .repo
+            .create_record(Object::from(
+                atrium_api::com::atproto::repo::create_record::InputData {
+                    collection: Nsid::from_str(atrium_api::app::bsky::graph::Listitem::NSID)?,
+                    record: KnownRecord::AppBskyGraphListitem(Box::new(Object::from(
+                        atrium_api::app::bsky::graph::listitem::RecordData {
+                            created_at: Datetime::now(),
+                            list: uri,
+                            subject,
+                        },
+                    )))
+                    .try_into_unknown()?,
+                    repo: self.id.clone(),
+                    rkey: None,
+                    swap_commit: None,
+                    validate: None,
+                },
+            ))
+            .await?;
+
+        Ok(())
+    }
+
+    /// Mutes everyone on a moderation list, or unmutes them if the list is
+    /// already muted.
+    pub async fn mute_list(self, did: &str, rkey: &str) -> Result<(), Box<dyn std::error::Error>> {
+        let list = self.list_view(did, rkey).await?;
+        let uri = list_uri(did, rkey);
+
+        if list.muted {
+            self.agent
+                .api
+                .app
+                .bsky
+                .graph
+                .unmute_actor_list(Object::from(
+                    atrium_api::app::bsky::graph::unmute_actor_list::InputData { list: uri },
+                ))
+                .await?;
+        } else {
+            self.agent
+                .api
+                .app
+                .bsky
+                .graph
+                .mute_actor_list(Object::from(
+                    atrium_api::app::bsky::graph::mute_actor_list::InputData { list: uri },
+                ))
+                .await?;
+        }
+
+        Ok(())
+    }
+
+    /// Blocks everyone on a moderation list, or unblocks them if the list is
+    /// already blocked.
+    pub async fn block_list(self, did: &str, rkey: &str) -> Result<(), Box<dyn std::error::Error>> {
+        let list = self.list_view(did, rkey).await?;
+
+        if let Some(block) = list.blocked {
+            return self
+                .delete_record(
+                    atrium_api::app::bsky::graph::Listblock::NSID,
+                    block.split('/').next_back().unwrap(),
+                )
+                .await;
+        }
+
+        self.agent
+            .api
+            .com
+            .atproto
+            .repo
+            .create_record(Object::from(
+                atrium_api::com::atproto::repo::create_record::InputData {
+                    collection: Nsid::from_str(atrium_api::app::bsky::graph::Listblock::NSID)?,
+                    record: KnownRecord::AppBskyGraphListblock(Box::new(Object::from(
+                        atrium_api::app::bsky::graph::listblock::RecordData {
+                            created_at: Datetime::now(),
+                            subject: list_uri(did, rkey),
+                        },
+                    )))
+                    .try_into_unknown()?,
+                    repo: self.id.clone(),
+                    rkey: None,
+                    swap_commit: None,
+                    validate: None,
+                },
+            ))
+            .await?;
+
+        Ok(())
+    }
+
+    /// Follows everyone in a starter pack who isn't followed yet.
+    pub async fn follow_all(self, did: &str, rkey: &str) -> Result<(), Box<dyn std::error::Error>> {
+        let pack = self
+            .agent
+            .api
+            .app
+            .bsky
+            .graph
+            .get_starter_pack(Object::from(
+                atrium_api::app::bsky::graph::get_starter_pack::ParametersData {
+                    starter_pack: starter_pack_uri(did, rkey),
+                },
+            ))
+            .await?;
+        let Some(list) = &pack.starter_pack.list else {
+            return Ok(());
+        };
+        let own = self.did()?.clone();
+
+        let members = self.list_members(&list.uri).await?;
+        for item in members {
+            let following = item
+                .subject
+                .viewer
+                .as_ref()
+                .is_some_and(|v| v.following.is_some());
+            if following || item.subject.did == own {
+                continue;
+            }
+
+            self.agent
+                .api
+                .com
+                .atproto
+                .repo
+                .create_record(Object::from(
+                    atrium_api::com::atproto::repo::create_record::InputData {
+                        collection: Nsid::from_str(atrium_api::app::bsky::graph::Follow::NSID)?,
+                        record: atrium_api::app::bsky::graph::follow::RecordData {
+                            created_at: Datetime::now(),
+                            subject: item.subject.did.clone(),
+                        }
+                        .try_into_unknown()?,
+                        repo: self.id.clone(),
+                        rkey: None,
+                        swap_commit: None,
+                        validate: None,
+                    },
+                ))
+                .await?;
+        }
+
+        Ok(())
+    }
+
+    /// Creates a starter pack from one of the user's lists, returning its DID
+    /// and record key.
+    pub async fn create_starter_pack(
+        self,
+        rkey: &str,
+        name: &str,
+        description: Option<&str>,
+    ) -> Result<(String, String), Box<dyn std::error::Error>> {
+        let did = self.did()?.to_string();
+        let record = self
+            .agent
+            .api
+            .com
+            .atproto
+            .repo
+            .create_record(Object::from(
+                atrium_api::com::atproto::repo::create_record::InputData {
+                    collection: Nsid::from_str(atrium_api::app::bsky::graph::Starterpack::NSID)?,
+                    record: KnownRecord::AppBskyGraphStarterpack(Box::new(Object::from(
+                        atrium_api::app::bsky::graph::starterpack::RecordData {
+                            created_at: Datetime::now(),
+                            description: description.map(String::from),
+                            description_facets: None,
+                            feeds: None,
+                            list: list_uri(&did, rkey),
+                            name: name.to_string(),
+                        },
+                    )))
+                    .try_into_unknown()?,
+                    repo: self.id.clone(),
+                    rkey: None,
+                    swap_commit: None,
+                    validate: None,
+                },
+            ))
+            .await?;
+
+        Ok(at_uri(&record.uri).ok_or("invalid record URI")?)
+    }
+
+    pub async fn muted_words(self) -> Result<Vec<Word>, Box<dyn std::error::Error>> {
+        Ok(moderation::muted_words(&self.preferences().await?)
+            .iter()
+            .map(|word| Word {
+                value: word.value.clone(),
+                tags_only: !word.targets.iter().any(|t| t == "content"),
+                expires: word
+                    .expires_at
+                    .as_ref()
+                    .map(|e| e.as_ref().format("%Y-%m-%d %H:%M").to_string()),
+                not_following: word.actor_target.as_deref() == Some("exclude-following"),
+            })
+            .collect())
+    }
+
+    pub async fn mute_word(
+        self,
+        value: &str,
+        tags_only: bool,
+        duration: Option<TimeDelta>,
+        not_following: bool,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        let mut preferences = self.preferences().await?;
+        let mut words = moderation::muted_words(&preferences);
+
+        words.push(Object::from(
+            atrium_api::app::bsky::actor::defs::MutedWordData {
+                actor_target: Some(String::from(if not_following {
+                    "exclude-following"
+                } else {
+                    "all"
+                })),
+                expires_at: duration.map(|d| Datetime::new(*Datetime::now().as_ref() + d)),
+                id: None,
+                targets: if tags_only {
+                    vec![String::from("tag")]
+                } else {
+                    vec![String::from("content"), String::from("tag")]
+                },
+                value: value.to_string(),
+            },
+        ));
+
+        Self::replace_muted_words(&mut preferences, words);
+        self.put_preferences(preferences).await
+    }
+
+    pub async fn unmute_word(self, value: &str) -> Result<(), Box<dyn std::error::Error>> {
+        let mut preferences = self.preferences().await?;
+        let mut words = moderation::muted_words(&preferences);
+
+        words.retain(|w| w.value != value);
+
+        Self::replace_muted_words(&mut preferences, words);
+        self.put_preferences(preferences).await
+    }
+
+    fn replace_muted_words(
+        preferences: &mut Vec<Union<PreferencesItem>>,
+        words: Vec<atrium_api::app::bsky::actor::defs::MutedWord>,
+    ) {
+        preferences.retain(|p| !matches!(p, Union::Refs(PreferencesItem::MutedWordsPref(_))));
+        preferences.push(Union::Refs(PreferencesItem::MutedWordsPref(Box::new(
+            Object::from(atrium_api::app::bsky::actor::defs::MutedWordsPrefData { items: words }),
+        ))));
+    }
+
+    /// Lists the user's notifications, grouping likes, reposts and follows
+    /// on the same subject. Only the given reasons are kept, unless none are
+    /// given. Viewing the latest notifications unfiltered marks them as seen.
+    pub async fn notifications(
+        self,
+        reasons: &[&str],
+        cursor: Option<String>,
+    ) -> Result<(Vec<Notification>, Option<String>), Box<dyn std::error::Error>> {
+        let moderation = self.moderation(&self.preferences().await?);
+        let seen_at = Datetime::now();
+        let latest = cursor.is_none();
+
+        let page = self
+            .agent
+            .api
+            .app
+            .bsky
+            .notification
+            .list_notifications(Object::from(
+                atrium_api::app::bsky::notification::list_notifications::ParametersData {
+                    cursor,
+                    limit: LimitedNonZeroU8::try_from(50).ok(),
+                    priority: None,
+                    reasons: (!reasons.is_empty())
+                        .then(|| reasons.iter().map(|r| r.to_string()).collect()),
+                    seen_at: None,
+                },
+            ))
+            .await?;
+        let fetched = page.data.notifications;
+
+        let mut groups: Vec<(String, Option<String>, Vec<_>)> = Vec::new();
+        for notification in &fetched {
+            let reason = notification.reason.clone();
+            let subject = match reason.as_str() {
+                "like" | "repost" => notification.reason_subject.clone(),
+                "follow" | "starterpack-joined" => None,
+                _ => Some(notification.uri.clone()),
+            };
+            let grouped = matches!(reason.as_str(), "like" | "repost" | "follow");
+
+            match groups
+                .iter_mut()
+                .find(|(r, s, _)| grouped && r == &reason && s == &subject)
+            {
+                Some((_, _, items)) => items.push(notification),
+                None => groups.push((reason, subject, vec![notification])),
+            }
+        }
+
+        let mut uris: Vec<String> = groups.iter().filter_map(|(_, s, _)| s.clone()).collect();
+        uris.sort();
+        uris.dedup();
+        let mut views = Vec::new();
+        for chunk in uris.chunks(25) {
+            let posts = self
+                .agent
+                .api
+                .app
+                .bsky
+                .feed
+                .get_posts(Object::from(
+                    atrium_api::app::bsky::feed::get_posts::ParametersData {
+                        uris: chunk.to_vec(),
+                    },
+                ))
+                .await?;
+            views.extend(posts.data.posts);
+        }
+
+        let mut notifications = Vec::new();
+        for (reason, subject, items) in groups {
+            // Notifications about posts which were deleted or hidden by
+            // moderation are left out.
+            let post = match subject {
+                Some(uri) => {
+                    let Some(view) = views.iter().find(|v| v.uri == uri) else {
+                        continue;
+                    };
+                    let posts = self
+                        .push_posts(std::slice::from_ref(view), &moderation)
+                        .await;
+                    match posts.into_iter().next() {
+                        Some(post) => Some(post),
+                        None => continue,
+                    }
+                }
+                None => None,
+            };
+            let mut actors: Vec<Actor> = Vec::new();
+            for item in &items {
+                if !actors.iter().any(|a| a.id == item.author.handle.as_str()) {
+                    actors.push(Actor::new(&item.author.handle, &item.author.display_name));
+                }
+            }
+
+            notifications.push(Notification {
+                reason,
+                actors,
+                post,
+                unread: items.iter().any(|n| !n.is_read),
+                date: items[0]
+                    .indexed_at
+                    .as_ref()
+                    .format("%Y-%m-%d %H:%M")
+                    .to_string(),
+            });
+        }
+
+        // Filtered pages leave out unread notifications, which shouldn't be
+        // marked as seen without being shown.
+        if latest && reasons.is_empty() {
+            self.agent
+                .api
+                .app
+                .bsky
+                .notification
+                .update_seen(Object::from(
+                    atrium_api::app::bsky::notification::update_seen::InputData { seen_at },
+                ))
+                .await?;
+        }
+
+        Ok((notifications, page.data.cursor))
+    }
+
+    pub async fn unread_notifications(self) -> Result<u64, Box<dyn std::error::Error>> {
+        let unread = self
+            .agent
+            .api
+            .app
+            .bsky
+            .notification
+            .get_unread_count(Object::from(
+                atrium_api::app::bsky::notification::get_unread_count::ParametersData {
+                    priority: None,
+                    seen_at: None,
+                },
+            ))
+            .await?;
+
+        Ok(unread.count.max(0) as u64)
+    }
+
+    /// Lists the user's direct message conversations, latest first.
+    pub async fn convos(
+        self,
+        cursor: Option<String>,
+    ) -> Result<(Vec<Convo>, Option<String>), Box<dyn std::error::Error>> {
+        let did = self.did()?.to_string();
+        let convos = self
+            .agent
+            .api_with_proxy(
+                Did::new(BSKY_CHAT_DID.to_string())?,
+                AtprotoServiceType::BskyChat,
+            )
+            .chat
+            .bsky
+            .convo
+            .list_convos(Object::from(
+                atrium_api::chat::bsky::convo::list_convos::ParametersData {
+                    cursor,
+                    limit: LimitedNonZeroU8::try_from(25).ok(),
+                },
+            ))
+            .await?;
+
+        Ok((
+            convos.convos.iter().map(|c| Convo::new(c, &did)).collect(),
+            convos.data.cursor,
+        ))
+    }
+
+    /// Gets a conversation along with a page of its messages, oldest first.
+    /// Viewing the latest messages marks the conversation as read.
+    pub async fn convo(
+        self,
+        id: &str,
+        cursor: Option<String>,
+    ) -> Result<(Convo, Vec<Message>, Option<String>), Box<dyn std::error::Error>> {
+        let did = self.did()?.to_string();
+        let chat = self.agent.api_with_proxy(
+            Did::new(BSKY_CHAT_DID.to_string())?,
+            AtprotoServiceType::BskyChat,
+        );
+        let convo = chat
+            .chat
+            .bsky
+            .convo
+            .get_convo(Object::from(
+                atrium_api::chat::bsky::convo::get_convo::ParametersData {
+                    convo_id: id.to_string(),
+                },
+            ))
+            .await?
+            .data
+            .convo;
+        let latest = cursor.is_none();
+        let page = chat
+            .chat
+            .bsky
+            .convo
+            .get_messages(Object::from(
+                atrium_api::chat::bsky::convo::get_messages::ParametersData {
+                    convo_id: id.to_string(),
+                    cursor,
+                    limit: LimitedNonZeroU8::try_from(25).ok(),
+                },
+            ))
+            .await?;
+
+        if latest && convo.unread_count > 0 {
+            self.mark_read(id).await?;
+        }
+
+        // Messages only name their sender's DID, so handles are taken from
+        // the members of the conversation.
+        let sender = |did: &Did| {
+            convo
+                .members
+                .iter()
+                .find(|m| &m.did == did)
+                .map_or(did.to_string(), |m| m.handle.to_string())
+        };
+        let mut messages: Vec<Message> = page
+            .messages
+            .iter()
+            .filter_map(|m| match m {
+                Union::Refs(OutputMessagesItem::ChatBskyConvoDefsMessageView(message)) => {
+                    Some(Message {
+                        sender: sender(&message.sender.did),
+                        body: Some(message.text.clone()),
+                        sent: message
+                            .sent_at
+                            .as_ref()
+                            .format("%Y-%m-%d %H:%M")
+                            .to_string(),
+                    })
+                }
+                Union::Refs(OutputMessagesItem::ChatBskyConvoDefsDeletedMessageView(message)) => {
+                    Some(Message {
+                        sender: sender(&message.sender.did),
+                        body: None,
+                        sent: message
+                            .sent_at
+                            .as_ref()
+                            .format("%Y-%m-%d %H:%M")
+                            .to_string(),
+                    })
+                }
+                Union::Unknown(_) => None,
+            })
+            .collect();
+        messages.reverse();
+
+        Ok((Convo::new(&convo, &did), messages, page.data.cursor))
+    }
+
+    /// Finds the conversation with an account, starting it if there's none.
+    pub async fn start_convo(self, id: &str) -> Result<String, Box<dyn std::error::Error>> {
+        let did = self.resolve_did(id).await?;
+        let convo = self
+            .agent
+            .api_with_proxy(
+                Did::new(BSKY_CHAT_DID.to_string())?,
+                AtprotoServiceType::BskyChat,
+            )
+            .chat
+            .bsky
+            .convo
+            .get_convo_for_members(Object::from(
+                atrium_api::chat::bsky::convo::get_convo_for_members::ParametersData {
+                    members: vec![did],
+                },
+            ))
+            .await?;
+
+        Ok(convo.data.convo.data.id)
+    }
+
+    pub async fn send_message(
+        self,
+        id: &str,
+        text: &str,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        self.agent
+            .api_with_proxy(
+                Did::new(BSKY_CHAT_DID.to_string())?,
+                AtprotoServiceType::BskyChat,
+            )
+            .chat
+            .bsky
+            .convo
+            .send_message(Object::from(
+                atrium_api::chat::bsky::convo::send_message::InputData {
+                    convo_id: id.to_string(),
+                    message: Object::from(atrium_api::chat::bsky::convo::defs::MessageInputData {
+                        embed: None,
+                        facets: None,
+                        text: text.to_string(),
+                    }),
+                },
+            ))
+            .await?;
+
+        Ok(())
+    }
+
+    /// Marks every message in a conversation as read.
+    pub async fn mark_read(&self, id: &str) -> Result<(), Box<dyn std::error::Error>> {
+        self.agent
+            .api_with_proxy(
+                Did::new(BSKY_CHAT_DID.to_string())?,
+                AtprotoServiceType::BskyChat,
+            )
+            .chat
+            .bsky
+            .convo
+            .update_read(Object::from(
+                atrium_api::chat::bsky::convo::update_read::InputData {
+                    convo_id: id.to_string(),
+                    message_id: None,
+                },
+            ))
+            .await?;
+
+        Ok(())
+    }
+
+    /// Fetches the given feed generators, in the order they were asked for.
+    async fn generators(
+        &self,
+        uris: Vec<String>,
+        saved: &[SavedFeedData],
+    ) -> Result<Vec<Generator>, Box<dyn std::error::Error>> {
+        if uris.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let views = self
+            .agent
+            .api
+            .app
+            .bsky
+            .feed
+            .get_feed_generators(Object::from(
+                atrium_api::app::bsky::feed::get_feed_generators::ParametersData {
+                    feeds: uris.clone(),
+                },
+            ))
+            .await?;
+
+        Ok(uris
+            .iter()
+            .filter_map(|uri| views.feeds.iter().find(|v| &v.uri == uri))
+            .map(|view| Generator::new(view, saved))
+            .collect())
+    }
+
+    /// Fetches the user's preferences, and keeps the labelers sent along
+    /// with every request in sync with them.
+    async fn preferences(&self) -> Result<Vec<Union<PreferencesItem>>, Box<dyn std::error::Error>> {
+        if self.guest {
+            return Ok(Vec::new());
+        }
+
+        let preferences = self
+            .agent
+            .api
+            .app
+            .bsky
+            .actor
+            .get_preferences(Object::from(
+                atrium_api::app::bsky::actor::get_preferences::ParametersData {},
+            ))
+            .await?;
+
+        self.configure_labelers(&moderation::labelers(&preferences.preferences))?;
+
+        Ok(preferences.data.preferences)
+    }
+
+    async fn put_preferences(
+        &self,
+        preferences: Vec<Union<PreferencesItem>>,
     ) -> Result<(), Box<dyn std::error::Error>> {
-        let hash_map = self.objects.lock().await;
-        let object = hash_map.get(id).unwrap();
-        let post = self
+        self.configure_labelers(&moderation::labelers(&preferences))?;
+
+        self.agent
+            .api
+            .app
+            .bsky
+            .actor
+            .put_preferences(Object::from(
+                atrium_api::app::bsky::actor::put_preferences::InputData { preferences },
+            ))
+            .await?;
+
+        Ok(())
+    }
+
+    fn moderation(&self, preferences: &[Union<PreferencesItem>]) -> Moderation {
+        if self.guest {
+            Moderation::guest()
+        } else {
+            Moderation::from_preferences(preferences)
+        }
+    }
+
+    fn configure_labelers(&self, labelers: &[Did]) -> Result<(), Box<dyn std::error::Error>> {
+        // Bluesky's own moderation always applies, and is allowed to redact
+        // content entirely.
+        let mut header = vec![(Did::new(BSKY_LABELER_DID.to_string())?, true)];
+        header.extend(
+            labelers
+                .iter()
+                .filter(|did| did.as_str() != BSKY_LABELER_DID)
+                .map(|did| (did.clone(), false)),
+        );
+
+        self.agent.configure_labelers_header(Some(header));
+        Ok(())
+    }
+
+    pub async fn langs(self) -> Vec<String> {
+        self.langs.lock().await.clone()
+    }
+
+    pub async fn set_langs(self, langs: Vec<String>) -> Result<(), Box<dyn std::error::Error>> {
+        let mut current = self.langs.lock().await;
+        Config::store(&self.fingerprint, "langs", &langs)?;
+        *current = langs;
+
+        Ok(())
+    }
+
+    pub async fn tags(self) -> Vec<String> {
+        self.tags.lock().await.clone()
+    }
+
+    /// Follows a hashtag, or unfollows it if already followed.
+    pub async fn follow_tag(self, tag: &str) -> Result<(), Box<dyn std::error::Error>> {
+        let mut tags = self.tags.lock().await;
+        let mut updated = tags.clone();
+
+        if let Some(position) = updated.iter().position(|t| t.eq_ignore_ascii_case(tag)) {
+            updated.remove(position);
+        } else {
+            updated.push(tag.to_string());
+        }
+
+        Config::store(&self.fingerprint, "tags", &updated)?;
+        *tags = updated;
+
+        Ok(())
+    }
+
+    pub async fn searches(self) -> Vec<String> {
+        self.searches.lock().await.clone()
+    }
+
+    /// Saves a search, or removes it if already saved.
+    pub async fn save_search(self, query: &str) -> Result<(), Box<dyn std::error::Error>> {
+        let mut searches = self.searches.lock().await;
+        let mut updated = searches.clone();
+
+        if let Some(position) = updated.iter().position(|s| s == query) {
+            updated.remove(position);
+        } else {
+            updated.push(query.to_string());
+        }
+
+        Config::store(&self.fingerprint, "searches", &updated)?;
+        *searches = updated;
+
+        Ok(())
+    }
+
+    /// Picks the languages for a new post: the ones chosen for the post
+    /// itself, then the user's defaults, then whatever can be detected from
+    /// the text.
+    async fn post_langs(
+        &self,
+        body: &str,
+        options: &PostOptions,
+    ) -> Result<Option<Vec<Language>>, Box<dyn std::error::Error>> {
+        let langs = if !options.langs.is_empty() {
+            options.langs.clone()
+        } else {
+            let defaults = self.langs.lock().await.clone();
+            if defaults.is_empty() {
+                detect_lang(body).into_iter().collect()
+            } else {
+                defaults
+            }
+        };
+
+        if langs.is_empty() {
+            return Ok(None);
+        }
+
+        Ok(Some(
+            langs
+                .iter()
+                .map(|l| Language::from_str(l).map_err(|_| format!("invalid language \"{l}\"")))
+                .collect::<Result<Vec<Language>, _>>()?,
+        ))
+    }
+
+    /// Returns the record key, list URI and subject of every list item in
+    /// the user's repository.
+    async fn list_items(&self) -> Result<Vec<(String, String, Did)>, Box<dyn std::error::Error>> {
+        let mut items = Vec::new();
+        let mut cursor = None;
+
+        loop {
+            let page = self
+                .agent
+                .api
+                .com
+                .atproto
+                .repo
+                .list_records(Object::from(
+                    atrium_api::com::atproto::repo::list_records::ParametersData {
+                        collection: Nsid::from_str(atrium_api::app::bsky::graph::Listitem::NSID)?,
+                        cursor,
+                        limit: LimitedNonZeroU8::try_from(100).ok(),
+                        repo: self.id.clone(),
+                        reverse: None,
+                        rkey_end: None,
+                        rkey_start: None,
+                    },
+                ))
+                .await?;
+
+            for record in &page.records {
+                if let KnownRecord::AppBskyGraphListitem(item) =
+                    KnownRecord::try_from_unknown(record.value.clone())?
+                {
+                    let rkey = at_uri(&record.uri).ok_or("invalid record URI")?.1;
+                    items.push((rkey, item.list.clone(), item.subject.clone()));
+                }
+            }
+
+            match page.data.cursor {
+                Some(next) if !page.data.records.is_empty() => cursor = Some(next),
+                _ => break,
+            }
+        }
+
+        Ok(items)
+    }
+
+    /// Fetches a list and the user's relationship to it, leaving its members
+    /// out.
+    async fn list_view(&self, did: &str, rkey: &str) -> Result<List, Box<dyn std::error::Error>> {
+        let list = self
             .agent
             .api
-            .com
-            .atproto
-            .repo
-            .get_record(Object::from(
-                atrium_api::com::atproto::repo::get_record::ParametersData {
-                    cid: Some(object.cid.clone()),
-                    collection: Nsid::from_str(atrium_api::app::bsky::feed::Post::NSID)?,
-                    repo: AtIdentifier::Did(Did::from_str(
-                        object.uri.split("/").skip(2).next().unwrap(),
-                    )?),
-                    rkey: object.uri.split("/").last().unwrap().to_string(),
+            .app
+            .bsky
+            .graph
+            .get_list(Object::from(
+                atrium_api::app::bsky::graph::get_list::ParametersData {
+                    cursor: None,
+                    limit: LimitedNonZeroU8::try_from(1).ok(),
+                    list: list_uri(did, rkey),
                 },
             ))
             .await?;
 
+        Ok(List::new(&list.data.list))
+    }
+
+    /// Returns everyone on a list, going through every page.
+    async fn list_members(
+        &self,
+        uri: &str,
+    ) -> Result<Vec<atrium_api::app::bsky::graph::defs::ListItemView>, Box<dyn std::error::Error>>
+    {
+        let mut members = Vec::new();
+        let mut cursor = None;
+
+        loop {
+            let page = self
+                .agent
+                .api
+                .app
+                .bsky
+                .graph
+                .get_list(Object::from(
+                    atrium_api::app::bsky::graph::get_list::ParametersData {
+                        cursor,
+                        limit: LimitedNonZeroU8::try_from(100).ok(),
+                        list: uri.to_string(),
+                    },
+                ))
+                .await?;
+
+            let empty = page.data.items.is_empty();
+            members.extend(page.data.items);
+            match page.data.cursor {
+                Some(next) if !empty => cursor = Some(next),
+                _ => break,
+            }
+        }
+
+        Ok(members)
+    }
+
+    /// Looks up a post seen earlier by its ID.
+    async fn object(&self, id: &str) -> Result<MainData, Box<dyn std::error::Error>> {
+        Ok(self
+            .objects
+            .lock()
+            .await
+            .get(id)
+            .ok_or("post not found")?
+            .clone())
+    }
+
+    async fn delete_record(
+        &self,
+        nsid: &str,
+        rkey: &str,
+    ) -> Result<(), Box<dyn std::error::Error>> {
         self.agent
             .api
             .com
             .atproto
             .repo
-            .create_record(Object::from(
-                atrium_api::com::atproto::repo::create_record::InputData {
-                    collection: Nsid::from_str(atrium_api::app::bsky::feed::Post::NSID)?,
-                    record: atrium_api::record::KnownRecord::AppBskyFeedPost(Box::from(
-                        Object::from(atrium_api::app::bsky::feed::post::RecordData {
-                            created_at: Datetime::now(),
-                            embed: None,
-                            entities: None,
-                            facets: None,
-                            labels: None,
-                            langs: None,
-                            reply: Some(Object::from(
-                                atrium_api::app::bsky::feed::post::ReplyRefData {
-                                    parent: Object::from(
-                                        atrium_api::com::atproto::repo::strong_ref::MainData {
-                                            cid: object.cid.clone(),
-                                            uri: object.uri.clone(),
-                                        },
-                                    ),
-                                    root: Object::from({
-                                        if let KnownRecord::AppBskyFeedPost(post_boxed) =
-                                            KnownRecord::try_from_unknown(post.value.clone())?
-                                        {
-                                            if let Some(reply) = Box::leak(post_boxed).reply.clone() {
-                                                reply
-                                                .root
-                                                .clone()
-                                            } else {
-                                                Object::from(atrium_api::com::atproto::repo::strong_ref::MainData {
-                                                    cid: object.cid.clone(),
-                                                    uri: object.uri.clone(),
-                                                })
-                                            }
-                                        } else {
-                                            return Err(Box::from("root not found"));
-                                        }
-                                    }),
-                                },
-                            )),
-                            tags: None,
-                            text: body.to_string(),
-                        }),
-                    ))
-                    .try_into_unknown()?,
+            .delete_record(Object::from(
+                atrium_api::com::atproto::repo::delete_record::InputData {
+                    collection: Nsid::from_str(nsid)?,
                     repo: self.id.clone(),
-                    rkey: None,
+                    rkey: rkey.to_string(),
                     swap_commit: None,
-                    validate: None,
+                    swap_record: None,
                 },
             ))
             .await?;
@@ -408,32 +3048,71 @@ impl Session {
         Ok(())
     }
 
-    pub async fn post<'a>(self, body: &'a str) -> Result<(), Box<dyn std::error::Error>> {
+    /// Rewrites the user's profile record. The write only goes through if
+    /// the record hasn't changed since it was read, so edits made elsewhere
+    /// in the meantime aren't lost.
+    async fn update_profile(
+        &self,
+        edit: impl FnOnce(&mut atrium_api::app::bsky::actor::profile::RecordData) + Send,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        let current = self
+            .agent
+            .api
+            .com
+            .atproto
+            .repo
+            .get_record(Object::from(
+                atrium_api::com::atproto::repo::get_record::ParametersData {
+                    cid: None,
+                    collection: Nsid::from_str(atrium_api::app::bsky::actor::Profile::NSID)?,
+                    repo: self.id.clone(),
+                    rkey: String::from("self"),
+                },
+            ))
+            .await;
+        let (mut profile, cid) = match current {
+            Ok(output) => match KnownRecord::try_from_unknown(output.data.value)? {
+                KnownRecord::AppBskyActorProfile(profile) => (*profile, output.data.cid),
+                _ => return Err(Box::from("invalid profile record")),
+            },
+            // Accounts which never edited their profile don't have a record.
+            Err(atrium_api::xrpc::Error::XrpcResponse(XrpcError {
+                error:
+                    Some(XrpcErrorKind::Custom(
+                        atrium_api::com::atproto::repo::get_record::Error::RecordNotFound(_),
+                    )),
+                ..
+            })) => (
+                Object::from(atrium_api::app::bsky::actor::profile::RecordData {
+                    avatar: None,
+                    banner: None,
+                    created_at: Some(Datetime::now()),
+                    description: None,
+                    display_name: None,
+                    joined_via_starter_pack: None,
+                    labels: None,
+                    pinned_post: None,
+                }),
+                None,
+            ),
+            Err(e) => return Err(e.into()),
+        };
+        edit(&mut profile.data);
+
         self.agent
             .api
             .com
             .atproto
             .repo
-            .create_record(Object::from(
-                atrium_api::com::atproto::repo::create_record::InputData {
-                    collection: Nsid::from_str(atrium_api::app::bsky::feed::Post::NSID)?,
-                    record: atrium_api::record::KnownRecord::AppBskyFeedPost(Box::from(
-                        Object::from(atrium_api::app::bsky::feed::post::RecordData {
-                            created_at: Datetime::now(),
-                            embed: None,
-                            entities: None,
-                            facets: None,
-                            labels: None,
-                            langs: None,
-                            reply: None,
-                            tags: None,
-                            text: body.to_string(),
-                        }),
-                    ))
-                    .try_into_unknown()?,
+            .put_record(Object::from(
+                atrium_api::com::atproto::repo::put_record::InputData {
+                    collection: Nsid::from_str(atrium_api::app::bsky::actor::Profile::NSID)?,
+                    record: KnownRecord::AppBskyActorProfile(Box::new(profile))
+                        .try_into_unknown()?,
                     repo: self.id.clone(),
-                    rkey: None,
+                    rkey: String::from("self"),
                     swap_commit: None,
+                    swap_record: cid,
                     validate: None,
                 },
             ))
@@ -441,4 +3120,206 @@ impl Session {
 
         Ok(())
     }
+
+    async fn upload_blob(&self, data: Vec<u8>) -> Result<BlobRef, Box<dyn std::error::Error>> {
+        Ok(self
+            .agent
+            .api
+            .com
+            .atproto
+            .repo
+            .upload_blob(data)
+            .await?
+            .data
+            .blob)
+    }
+
+    async fn resolve_did(&self, id: &str) -> Result<Did, Box<dyn std::error::Error>> {
+        Ok(match AtIdentifier::from_str(id)? {
+            AtIdentifier::Did(did) => did,
+            AtIdentifier::Handle(handle) => {
+                self.agent
+                    .api
+                    .com
+                    .atproto
+                    .identity
+                    .resolve_handle(Object::from(
+                        atrium_api::com::atproto::identity::resolve_handle::ParametersData {
+                            handle,
+                        },
+                    ))
+                    .await?
+                    .data
+                    .did
+            }
+        })
+    }
+
+    fn did(&self) -> Result<&Did, Box<dyn std::error::Error>> {
+        match &self.id {
+            AtIdentifier::Did(did) => Ok(did),
+            AtIdentifier::Handle(_) => Err(Box::from("session has no DID")),
+        }
+    }
+}
+
+fn self_labels(
+    options: &PostOptions,
+) -> Option<Union<atrium_api::app::bsky::feed::post::RecordLabelsRefs>> {
+    if options.labels.is_empty() {
+        return None;
+    }
+
+    Some(Union::Refs(
+        atrium_api::app::bsky::feed::post::RecordLabelsRefs::ComAtprotoLabelDefsSelfLabels(
+            Box::new(Object::from(
+                atrium_api::com::atproto::label::defs::SelfLabelsData {
+                    values: options
+                        .labels
+                        .iter()
+                        .map(|l| {
+                            Object::from(atrium_api::com::atproto::label::defs::SelfLabelData {
+                                val: l.clone(),
+                            })
+                        })
+                        .collect(),
+                },
+            )),
+        ),
+    ))
+}
+
+fn list_uri(did: &str, rkey: &str) -> String {
+    format!(
+        "at://{did}/{}/{rkey}",
+        atrium_api::app::bsky::graph::List::NSID
+    )
+}
+
+fn starter_pack_uri(did: &str, rkey: &str) -> String {
+    format!(
+        "at://{did}/{}/{rkey}",
+        atrium_api::app::bsky::graph::Starterpack::NSID
+    )
+}
+
+fn generator_uri(did: &str, rkey: &str) -> String {
+    format!(
+        "at://{did}/{}/{rkey}",
+        atrium_api::app::bsky::feed::Generator::NSID
+    )
+}
+
+/// Returns the user's saved feeds. Older accounts which only have the first
+/// version of the preference get theirs converted.
+fn saved_feeds(preferences: &[Union<PreferencesItem>]) -> Vec<SavedFeedData> {
+    let v2 = preferences.iter().find_map(|p| match p {
+        Union::Refs(PreferencesItem::SavedFeedsPrefV2(pref)) => Some(pref),
+        _ => None,
+    });
+    if let Some(pref) = v2 {
+        return pref.items.iter().map(|i| i.data.clone()).collect();
+    }
+
+    let Some(pref) = preferences.iter().find_map(|p| match p {
+        Union::Refs(PreferencesItem::SavedFeedsPref(pref)) => Some(pref),
+        _ => None,
+    }) else {
+        return Vec::new();
+    };
+
+    let mut saved: Vec<SavedFeedData> = vec![SavedFeedData {
+        id: tid(),
+        pinned: true,
+        r#type: String::from("timeline"),
+        value: String::from("following"),
+    }];
+    for uri in pref.pinned.iter().chain(pref.saved.iter()) {
+        if !saved.iter().any(|s| &s.value == uri) {
+            saved.push(saved_feed(uri.clone(), pref.pinned.contains(uri)));
+        }
+    }
+
+    saved
+}
+
+fn saved_feed(uri: String, pinned: bool) -> SavedFeedData {
+    let r#type = match uri.contains(atrium_api::app::bsky::graph::List::NSID) {
+        true => "list",
+        false => "feed",
+    };
+
+    SavedFeedData {
+        id: tid(),
+        pinned,
+        r#type: r#type.to_string(),
+        value: uri,
+    }
+}
+
+/// Writes the saved feeds back. The first version of the preference is kept
+/// in sync for clients which still read it.
+fn replace_saved_feeds(preferences: &mut Vec<Union<PreferencesItem>>, saved: Vec<SavedFeedData>) {
+    let uris = |pinned: bool| -> Vec<String> {
+        saved
+            .iter()
+            .filter(|s| s.r#type != "timeline" && (s.pinned || !pinned))
+            .map(|s| s.value.clone())
+            .collect()
+    };
+    let (pinned, all) = (uris(true), uris(false));
+
+    for preference in preferences.iter_mut() {
+        if let Union::Refs(PreferencesItem::SavedFeedsPref(pref)) = preference {
+            pref.pinned = pinned.clone();
+            pref.saved = all.clone();
+        }
+    }
+
+    preferences.retain(|p| !matches!(p, Union::Refs(PreferencesItem::SavedFeedsPrefV2(_))));
+    preferences.push(Union::Refs(PreferencesItem::SavedFeedsPrefV2(Box::new(
+        Object::from(atrium_api::app::bsky::actor::defs::SavedFeedsPrefV2Data {
+            items: saved.into_iter().map(Object::from).collect(),
+        }),
+    ))));
+}
+
+/// Makes a timestamp identifier, as used for saved feed items. Identifiers
+/// made within the same microsecond are kept apart by bumping the timestamp.
+fn tid() -> String {
+    static LAST: AtomicU64 = AtomicU64::new(0);
+    const ALPHABET: &[u8] = b"234567abcdefghijklmnopqrstuvwxyz";
+
+    let now = chrono::Utc::now().timestamp_micros() as u64;
+    let micros = LAST
+        .fetch_update(Ordering::SeqCst, Ordering::SeqCst, |last| {
+            Some(now.max(last + 1))
+        })
+        .map_or(now, |last| now.max(last + 1));
+    let mut value = micros << 10;
+
+    let mut tid = [0u8; 13];
+    for c in tid.iter_mut().rev() {
+        *c = ALPHABET[(value & 0x1f) as usize];
+        value >>= 5;
+    }
+
+    String::from_utf8(tid.to_vec()).unwrap()
+}
+
+#[cfg(feature = "detect-lang")]
+fn detect_lang(text: &str) -> Option<String> {
+    let info = whatlang::detect(text)?;
+    if !info.is_reliable() {
+        return None;
+    }
+
+    isolang::Language::from_639_3(info.lang().code())
+        .and_then(|l| l.to_639_1())
+        .map(|l| l.to_string())
+}
+
+#[cfg(not(feature = "detect-lang"))]
+fn detect_lang(_text: &str) -> Option<String> {
+    None
 }
@@ -7,6 +7,8 @@ use tracing::{debug, info};
 #[derive(Clone)]
 pub struct State {
     pub sessions: Arc<HashMap<String, Session>>,
+    /// The read-only session for visitors, if guest mode is enabled.
+    pub guest: Option<Session>,
 }
 
 impl State {
@@ -15,15 +17,23 @@ impl State {
         let mut sessions: HashMap<String, Session> = HashMap::new();
 
         for (fingerprint, account) in &config.accounts {
-            let session = Session::new(&account, objects.clone()).await?;
+            let session = Session::new(fingerprint, account, objects.clone()).await?;
             debug!("session spawned for user @{}", &session.handle);
             sessions.insert(fingerprint.clone().to_lowercase(), session);
         }
 
         info!("{} sessions spawned", sessions.len());
 
+        let guest = if config.base.guest {
+            info!("guest mode enabled");
+            Some(Session::guest(objects)?)
+        } else {
+            None
+        };
+
         Ok(State {
             sessions: Arc::new(sessions),
+            guest,
         })
     }
 }
@@ -1,17 +1,30 @@
-use std::{collections::HashMap, ops::Deref, sync::Arc};
+use std::{collections::HashMap, fmt, ops::Deref, str::FromStr, sync::Arc};
 
 use askama::Template;
 use atrium_api::{
-    app::bsky::feed::defs::{
-        FeedViewPostData, FeedViewPostReasonRefs, PostViewEmbedRefs, ReplyRefParentRefs,
+    app::bsky::{
+        actor::defs::{ProfileView, SavedFeedData},
+        feed::defs::{
+            FeedViewPostData, FeedViewPostReasonRefs, GeneratorView, PostViewEmbedRefs,
+            ReplyRefParentRefs,
+        },
+        graph::defs::{ListView, CURATELIST, MODLIST, REFERENCELIST},
+        richtext::facet::MainFeaturesItem,
     },
+    chat::bsky::convo::defs::{ConvoViewData, ConvoViewLastMessageRefs},
     com::atproto::repo::strong_ref::MainData,
     record::KnownRecord,
-    types::{string::Handle, Object, TryFromUnknown, Union},
+    types::{
+        string::{AtIdentifier, Handle, Language},
+        Object, TryFromUnknown, Union, Unknown,
+    },
 };
 use blake3::Hasher;
+use chrono::{DateTime, FixedOffset, NaiveDate};
 use tokio::sync::Mutex;
 
+use crate::moderation::{Moderation, Visibility};
+
 #[derive(Debug)]
 pub enum Media {
     Image((String, String)),
@@ -25,24 +38,494 @@ pub struct Profile {
     pub id: Handle,
     pub name: String,
     pub bio: String,
+    pub avatar: Option<String>,
+    pub banner: Option<String>,
     pub followers: u64,
     pub follows: u64,
+    pub posts_count: u64,
+    /// The date the account was created on.
+    pub created: Option<String>,
     pub following: bool,
+    pub follows_you: bool,
+    pub labels: Vec<String>,
+    pub visibility: Visibility,
+    pub labeler: bool,
+    pub subscribed: bool,
+    pub muted: bool,
+    pub blocking: bool,
+    pub blocked_by: bool,
+    pub pinned: Option<Post>,
     pub posts: Vec<Post>,
+    pub suggestions: Vec<Actor>,
+    pub starter_packs: Vec<StarterPack>,
+}
+
+/// A change to the user's own profile. Empty names and descriptions clear
+/// them, while images are given as uploaded.
+#[derive(Debug)]
+pub enum ProfileEdit {
+    Name(String),
+    Bio(String),
+    Avatar(Vec<u8>),
+    Banner(Vec<u8>),
+}
+
+#[derive(Debug)]
+pub struct Search {
+    pub actors: Vec<Actor>,
+    pub posts: Vec<Post>,
+    pub cursor: Option<String>,
+}
+
+/// A feed generator, along with whether the user saved or pinned it.
+#[derive(Debug)]
+pub struct Generator {
+    pub did: String,
+    pub rkey: String,
+    pub name: String,
+    pub creator: String,
+    pub description: String,
+    pub likes: usize,
+    pub liked: bool,
+    pub saved: bool,
+    pub pinned: bool,
+}
+
+impl Generator {
+    pub fn new(view: &GeneratorView, saved: &[SavedFeedData]) -> Generator {
+        let (did, rkey) = at_uri(&view.uri).unwrap_or_default();
+        let item = saved.iter().find(|s| s.value == view.uri);
+
+        Generator {
+            did,
+            rkey,
+            name: view.display_name.clone(),
+            creator: view.creator.handle.to_string(),
+            description: view.description.clone().unwrap_or_default(),
+            likes: view.like_count.unwrap_or(0),
+            liked: view.viewer.as_ref().is_some_and(|v| v.like.is_some()),
+            saved: item.is_some(),
+            pinned: item.is_some_and(|s| s.pinned),
+        }
+    }
+
+    pub fn path(&self) -> String {
+        format!("/f/{}/{}", self.did, self.rkey)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ListPurpose {
+    Curation,
+    Moderation,
+    Reference,
+}
+
+impl AsRef<str> for ListPurpose {
+    fn as_ref(&self) -> &str {
+        match self {
+            ListPurpose::Curation => CURATELIST,
+            ListPurpose::Moderation => MODLIST,
+            ListPurpose::Reference => REFERENCELIST,
+        }
+    }
+}
+
+impl fmt::Display for ListPurpose {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            ListPurpose::Curation => "curation list",
+            ListPurpose::Moderation => "moderation list",
+            ListPurpose::Reference => "reference list",
+        })
+    }
+}
+
+#[derive(Debug)]
+pub struct List {
+    pub did: String,
+    pub rkey: String,
+    pub name: String,
+    pub purpose: ListPurpose,
+    pub creator: String,
+    pub description: String,
+    pub members: usize,
+    pub muted: bool,
+    /// The URI of the user's block on this list, if any.
+    pub blocked: Option<String>,
+}
+
+impl List {
+    pub fn new(view: &ListView) -> List {
+        let (did, rkey) = at_uri(&view.uri).unwrap_or_default();
+        let purpose = match view.purpose.as_str() {
+            CURATELIST => ListPurpose::Curation,
+            MODLIST => ListPurpose::Moderation,
+            _ => ListPurpose::Reference,
+        };
+
+        List {
+            did,
+            rkey,
+            name: view.name.clone(),
+            purpose,
+            creator: view.creator.handle.to_string(),
+            description: view.description.clone().unwrap_or_default(),
+            members: view.list_item_count.unwrap_or(0),
+            muted: view.viewer.as_ref().and_then(|v| v.muted).unwrap_or(false),
+            blocked: view.viewer.as_ref().and_then(|v| v.blocked.clone()),
+        }
+    }
+
+    pub fn path(&self) -> String {
+        format!("/ls/{}/{}", self.did, self.rkey)
+    }
+}
+
+#[derive(Debug)]
+pub struct StarterPack {
+    pub did: String,
+    pub rkey: String,
+    pub name: String,
+    pub creator: String,
+    pub description: String,
+    pub joined: usize,
+}
+
+impl StarterPack {
+    pub fn new(
+        uri: &str,
+        creator: &Handle,
+        record: &Unknown,
+        joined: Option<usize>,
+    ) -> StarterPack {
+        let (did, rkey) = at_uri(uri).unwrap_or_default();
+        let (name, description) = match KnownRecord::try_from_unknown(record.clone()) {
+            Ok(KnownRecord::AppBskyGraphStarterpack(record)) => {
+                (record.name.clone(), record.description.clone())
+            }
+            _ => (String::new(), None),
+        };
+
+        StarterPack {
+            did,
+            rkey,
+            name,
+            creator: creator.to_string(),
+            description: description.unwrap_or_default(),
+            joined: joined.unwrap_or(0),
+        }
+    }
+
+    pub fn path(&self) -> String {
+        format!("/sp/{}/{}", self.did, self.rkey)
+    }
+}
+
+/// Splits an AT URI into its authority and record key.
+pub fn at_uri(uri: &str) -> Option<(String, String)> {
+    let mut parts = uri.strip_prefix("at://")?.split('/');
+
+    match (parts.next(), parts.next(), parts.next()) {
+        (Some(authority), Some(_), Some(rkey)) => Some((authority.to_string(), rkey.to_string())),
+        _ => None,
+    }
+}
+
+/// A search query, with its operators split from the text.
+#[derive(Debug, Default, Clone)]
+pub struct Query {
+    pub text: String,
+    pub author: Option<String>,
+    pub mentions: Option<String>,
+    pub since: Option<String>,
+    pub until: Option<String>,
+    pub lang: Option<String>,
+    pub domain: Option<String>,
+    pub tags: Vec<String>,
+}
+
+impl Query {
+    /// Whether the query is plain text, without any operators.
+    pub fn is_plain(&self) -> bool {
+        self.author.is_none()
+            && self.mentions.is_none()
+            && self.since.is_none()
+            && self.until.is_none()
+            && self.lang.is_none()
+            && self.domain.is_none()
+            && self.tags.is_empty()
+    }
+
+    pub fn tag(tag: &str) -> Query {
+        Query {
+            text: format!("#{tag}"),
+            tags: vec![tag.to_string()],
+            ..Default::default()
+        }
+    }
+}
+
+impl FromStr for Query {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut query = Query::default();
+        let mut words = Vec::new();
+
+        for token in s.split_whitespace() {
+            let Some((operator, value)) = token.split_once(':') else {
+                words.push(token);
+                continue;
+            };
+
+            match operator {
+                "from" | "mentions" => {
+                    let value = value.trim_start_matches('@');
+                    if value != "me" && AtIdentifier::from_str(value).is_err() {
+                        return Err(format!("invalid account \"{value}\""));
+                    }
+                    match operator {
+                        "from" => query.author = Some(value.to_string()),
+                        _ => query.mentions = Some(value.to_string()),
+                    }
+                }
+                "since" | "until" => {
+                    let valid = NaiveDate::parse_from_str(value, "%Y-%m-%d").is_ok()
+                        || DateTime::parse_from_rfc3339(value).is_ok();
+                    if !valid {
+                        return Err(format!("invalid date \"{value}\""));
+                    }
+                    match operator {
+                        "since" => query.since = Some(value.to_string()),
+                        _ => query.until = Some(value.to_string()),
+                    }
+                }
+                "lang" => {
+                    if Language::from_str(value).is_err() {
+                        return Err(format!("invalid language \"{value}\""));
+                    }
+                    query.lang = Some(value.to_string());
+                }
+                "domain" => query.domain = Some(value.to_string()),
+                // Anything else, such as a link, is part of the text.
+                _ => words.push(token),
+            }
+        }
+
+        // The query text can't be empty, so a wildcard stands in for it when
+        // only operators are given, which are sent on their own.
+        query.text = match words.is_empty() {
+            true => String::from("*"),
+            false => words.join(" "),
+        };
+
+        Ok(query)
+    }
+}
+
+#[derive(Debug)]
+pub struct Word {
+    pub value: String,
+    /// Whether only hashtags are matched, rather than the whole text.
+    pub tags_only: bool,
+    pub expires: Option<String>,
+    /// Whether the word only applies to accounts the user doesn't follow.
+    pub not_following: bool,
+}
+
+#[derive(Debug)]
+pub struct Actor {
+    pub id: String,
+    pub name: String,
+    /// Whether the user and this account follow each other.
+    pub mutual: bool,
+    /// Whether the user follows this account.
+    pub following: bool,
+}
+
+impl Actor {
+    pub fn new(handle: &Handle, display_name: &Option<String>) -> Actor {
+        Actor {
+            id: handle.to_string(),
+            name: display_name
+                .clone()
+                .filter(|n| !n.is_empty())
+                .unwrap_or(handle.to_string()),
+            mutual: false,
+            following: false,
+        }
+    }
+
+    /// Same as [`Actor::new`], also telling whether the user follows the
+    /// account, and whether it's a mutual.
+    pub fn from_profile(profile: &ProfileView) -> Actor {
+        Actor {
+            following: profile
+                .viewer
+                .as_ref()
+                .is_some_and(|v| v.following.is_some()),
+            mutual: profile
+                .viewer
+                .as_ref()
+                .is_some_and(|v| v.following.is_some() && v.followed_by.is_some()),
+            ..Actor::new(&profile.handle, &profile.display_name)
+        }
+    }
+}
+
+/// A direct message conversation.
+#[derive(Debug)]
+pub struct Convo {
+    pub id: String,
+    /// The other members of the conversation.
+    pub members: Vec<Actor>,
+    /// The text of the latest message.
+    pub last: Option<String>,
+    pub unread: u64,
+    pub muted: bool,
+}
+
+impl Convo {
+    pub fn new(view: &ConvoViewData, own: &str) -> Convo {
+        Convo {
+            id: view.id.clone(),
+            members: view
+                .members
+                .iter()
+                .filter(|m| m.did.as_str() != own)
+                .map(|m| Actor::new(&m.handle, &m.display_name))
+                .collect(),
+            last: match &view.last_message {
+                Some(Union::Refs(ConvoViewLastMessageRefs::MessageView(message))) => {
+                    Some(message.text.clone())
+                }
+                Some(Union::Refs(ConvoViewLastMessageRefs::DeletedMessageView(_))) => {
+                    Some(String::from("(deleted)"))
+                }
+                _ => None,
+            },
+            unread: view.unread_count.max(0) as u64,
+            muted: view.muted,
+        }
+    }
+
+    /// Names the conversation after its members.
+    pub fn title(&self) -> String {
+        if self.members.is_empty() {
+            return String::from("Just you");
+        }
+
+        self.members
+            .iter()
+            .map(|m| format!("@{}", m.id))
+            .collect::<Vec<_>>()
+            .join(", ")
+    }
+}
+
+/// A notification, or several of them when likes, reposts or follows on the
+/// same subject are grouped together.
+#[derive(Debug)]
+pub struct Notification {
+    pub reason: String,
+    /// The accounts behind the notification, latest first.
+    pub actors: Vec<Actor>,
+    /// The liked or reposted post, or the reply, mention or quote itself.
+    pub post: Option<Post>,
+    pub unread: bool,
+    pub date: String,
+}
+
+impl Notification {
+    /// Describes the notification, such as "@a and 4 others liked your post".
+    pub fn summary(&self) -> String {
+        let who = match self.actors.as_slice() {
+            [] => String::from("Someone"),
+            [actor] => format!("@{}", actor.id),
+            [actor, others @ ..] => format!(
+                "@{} and {} other{}",
+                actor.id,
+                others.len(),
+                if others.len() == 1 { "" } else { "s" }
+            ),
+        };
+        let what = match self.reason.as_str() {
+            "like" => "liked your post",
+            "repost" => "reposted your post",
+            "follow" => "followed you",
+            "mention" => "mentioned you",
+            "reply" => "replied to you",
+            "quote" => "quoted your post",
+            "starterpack-joined" => "joined through your starter pack",
+            _ => "interacted with you",
+        };
+
+        format!("{who} {what}")
+    }
+}
+
+/// A message in a conversation. Deleted messages have no body.
+#[derive(Debug)]
+pub struct Message {
+    pub sender: String,
+    pub body: Option<String>,
+    pub sent: String,
 }
 
 #[derive(Debug, Template)]
 #[template(path = "components/post.gmi", escape = "txt")]
 pub struct Post {
     pub id: String,
+    /// The AT URI of the post.
+    pub uri: String,
     pub username: String,
     pub body: String,
     pub media: Option<Media>,
     pub replies: u64,
     pub reposts: u64,
     pub likes: u64,
+    pub quotes: u64,
     pub viewer: Viewer,
     pub context: PostContext,
+    pub tags: Vec<String>,
+    pub labels: Vec<String>,
+    pub visibility: Visibility,
+    pub date: DateTime<FixedOffset>,
+}
+
+impl Post {
+    /// A path to the post which, unlike the one built from its ID, stays
+    /// valid across restarts.
+    pub fn permalink(&self) -> String {
+        let (did, rkey) = at_uri(&self.uri).unwrap_or_default();
+        format!("/@{did}/p/{rkey}")
+    }
+
+    /// A one line summary of the post, used as the title of feed entries.
+    pub fn title(&self) -> String {
+        if self.visibility == Visibility::Warn {
+            return format!("@{}: ⚠️ Content warning", self.username);
+        }
+
+        let line = self.body.lines().find(|l| !l.trim().is_empty());
+        let mut title: String = line.unwrap_or("").chars().take(80).collect();
+        if line.is_some_and(|l| l.chars().count() > 80) {
+            title.push('…');
+        }
+        if title.is_empty() {
+            title = String::from(if self.media.is_some() {
+                "(media)"
+            } else {
+                "(no text)"
+            });
+        }
+
+        match &self.context {
+            PostContext::Repost(reposter) => format!("@{reposter} 🔁 @{}: {title}", self.username),
+            _ => format!("@{}: {title}", self.username),
+        }
+    }
 }
 
 #[derive(Debug)]
@@ -55,19 +538,156 @@ pub struct Quote {
 pub struct Viewer {
     pub liked: bool,
     pub reposted: bool,
+    pub thread_muted: bool,
+    /// Whether the post is seen by a visitor who can't interact with it.
+    pub guest: bool,
 }
 
 #[derive(Debug)]
 pub enum PostContext {
     Reply(String),
     Repost(String),
+    Pinned,
     None,
 }
 
+#[derive(Debug, Clone, PartialEq)]
+pub enum ReplyRule {
+    Mentioned,
+    Followers,
+    Following,
+    List(String),
+}
+
+/// Self-labels an author can attach to their own posts.
+pub static SELF_LABELS: [&str; 4] = ["sexual", "nudity", "porn", "graphic-media"];
+
+/// Options set while composing a post, written as a list of words such as
+/// "mentioned following noquotes".
+#[derive(Debug, Clone)]
+pub struct PostOptions {
+    /// Who can reply. `None` lets everyone reply, an empty list nobody.
+    pub replies: Option<Vec<ReplyRule>>,
+    pub quotes: bool,
+    /// Languages overriding the user's defaults for this post.
+    pub langs: Vec<String>,
+    /// Content warnings attached to the post.
+    pub labels: Vec<String>,
+}
+
+impl Default for PostOptions {
+    fn default() -> Self {
+        PostOptions {
+            replies: None,
+            quotes: true,
+            langs: Vec::new(),
+            labels: Vec::new(),
+        }
+    }
+}
+
+impl FromStr for PostOptions {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut options = PostOptions::default();
+
+        for token in s
+            .split(|c: char| c.is_whitespace() || c == ',')
+            .filter(|t| !t.is_empty())
+        {
+            let rule = match token {
+                "everyone" => {
+                    options.replies = None;
+                    continue;
+                }
+                "nobody" => {
+                    options.replies = Some(Vec::new());
+                    continue;
+                }
+                "noquotes" => {
+                    options.quotes = false;
+                    continue;
+                }
+                "mentioned" => ReplyRule::Mentioned,
+                "followers" => ReplyRule::Followers,
+                "following" => ReplyRule::Following,
+                _ => {
+                    if let Some(lang) = token.strip_prefix("lang:") {
+                        if Language::from_str(lang).is_err() {
+                            return Err(format!("invalid language \"{lang}\""));
+                        }
+                        if options.langs.len() == 3 {
+                            return Err(String::from("a post can have at most 3 languages"));
+                        }
+                        options.langs.push(lang.to_string());
+                        continue;
+                    }
+
+                    if let Some(label) = token.strip_prefix("label:") {
+                        if !SELF_LABELS.contains(&label) {
+                            return Err(format!("unknown label \"{label}\""));
+                        }
+                        if !options.labels.iter().any(|l| l == label) {
+                            options.labels.push(label.to_string());
+                        }
+                        continue;
+                    }
+
+                    match token.strip_prefix("list:") {
+                        Some(rkey) if !rkey.is_empty() => ReplyRule::List(rkey.to_string()),
+                        _ => return Err(format!("unknown option \"{token}\"")),
+                    }
+                }
+            };
+
+            let rules = options.replies.get_or_insert_with(Vec::new);
+            if !rules.contains(&rule) {
+                rules.push(rule);
+            }
+        }
+
+        Ok(options)
+    }
+}
+
+impl fmt::Display for PostOptions {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut tokens: Vec<String> = match &self.replies {
+            None => vec![String::from("everyone")],
+            Some(rules) if rules.is_empty() => vec![String::from("nobody")],
+            Some(rules) => rules
+                .iter()
+                .map(|rule| match rule {
+                    ReplyRule::Mentioned => String::from("mentioned"),
+                    ReplyRule::Followers => String::from("followers"),
+                    ReplyRule::Following => String::from("following"),
+                    ReplyRule::List(rkey) => format!("list:{rkey}"),
+                })
+                .collect(),
+        };
+
+        if !self.quotes {
+            tokens.push(String::from("noquotes"));
+        }
+
+        for lang in &self.langs {
+            tokens.push(format!("lang:{lang}"));
+        }
+
+        for label in &self.labels {
+            tokens.push(format!("label:{label}"));
+        }
+
+        write!(f, "{}", tokens.join(","))
+    }
+}
+
 impl Post {
     pub async fn push(
         post: &Object<FeedViewPostData>,
         objects: &Arc<Mutex<HashMap<String, MainData>>>,
+        moderation: &Moderation,
     ) -> Post {
         // Create a hash to use in URIs
         let mut hasher = Hasher::new();
@@ -82,27 +702,47 @@ impl Post {
             },
         );
 
+        let record = match KnownRecord::try_from_unknown(post.post.record.clone()).unwrap() {
+            KnownRecord::AppBskyFeedPost(record) => Some(record),
+            _ => None,
+        };
+        let text = record.as_ref().map(|r| r.text.clone()).unwrap_or_default();
+        let tags = record.as_ref().map(|r| tags(r)).unwrap_or_default();
+
+        let (labels, mut visibility) = moderation.labels(
+            &post
+                .post
+                .labels
+                .iter()
+                .chain(post.post.author.labels.iter())
+                .flatten()
+                .cloned()
+                .collect::<Vec<_>>(),
+        );
+        let following = post
+            .post
+            .author
+            .viewer
+            .as_ref()
+            .is_some_and(|v| v.following.is_some());
+        if moderation.muted(&text, &tags, following) {
+            visibility = Visibility::Hide;
+        }
+
         Post {
             id: hash.to_string(),
+            uri: post.post.uri.clone(),
             username: post.post.author.handle.as_str().to_string(),
-            body: if let KnownRecord::AppBskyFeedPost(body) =
-                KnownRecord::try_from_unknown(post.post.record.clone()).unwrap()
-            {
-                Box::leak(body)
-                    .text
-                    .clone()
-                    .chars()
-                    .map(|v| if v == '#' { '♯' } else { v })
-                    .collect::<String>()
-            } else {
-                String::new()
-            },
-            media: post.post.embed.clone().map_or(None, |v| match v {
+            body: text
+                .chars()
+                .map(|v| if v == '#' { '♯' } else { v })
+                .collect::<String>(),
+            media: post.post.embed.clone().and_then(|v| match v {
                 Union::Refs(r) => match r {
                     // TODO(otoayana): Add multiple media items
                     PostViewEmbedRefs::AppBskyEmbedImagesView(image) => {
                         let image_data = Box::leak(image).data.images.first().unwrap().data.clone();
-                        let alt = if image_data.alt.len() > 0 {
+                        let alt = if !image_data.alt.is_empty() {
                             image_data
                                 .alt
                                 .chars()
@@ -149,23 +789,234 @@ impl Post {
             replies: post.post.reply_count.unwrap_or(0) as u64,
             reposts: post.post.repost_count.unwrap_or(0) as u64,
             likes: post.post.like_count.unwrap_or(0) as u64,
-            context: if let Some(Union::Refs(FeedViewPostReasonRefs::ReasonRepost(r))) =
-                post.reason.clone()
-            {
-                PostContext::Repost(r.deref().by.handle.to_string())
-            } else {
-                if let Some(Union::Refs(ReplyRefParentRefs::PostView(reply))) =
-                    post.reply.clone().map(|v| v.parent.clone())
-                {
-                    PostContext::Reply(reply.author.handle.to_string())
-                } else {
-                    PostContext::None
+            quotes: post.post.quote_count.unwrap_or(0) as u64,
+            context: match post.reason.clone() {
+                Some(Union::Refs(FeedViewPostReasonRefs::ReasonRepost(r))) => {
+                    PostContext::Repost(r.deref().by.handle.to_string())
+                }
+                Some(Union::Refs(FeedViewPostReasonRefs::ReasonPin(_))) => PostContext::Pinned,
+                _ => {
+                    if let Some(Union::Refs(ReplyRefParentRefs::PostView(reply))) =
+                        post.reply.clone().map(|v| v.parent.clone())
+                    {
+                        PostContext::Reply(reply.author.handle.to_string())
+                    } else {
+                        PostContext::None
+                    }
                 }
             },
             viewer: Viewer {
-                liked: post.post.viewer.clone().unwrap().like.is_some(),
-                reposted: post.post.viewer.clone().unwrap().repost.is_some(),
+                liked: post.post.viewer.as_ref().is_some_and(|v| v.like.is_some()),
+                reposted: post
+                    .post
+                    .viewer
+                    .as_ref()
+                    .is_some_and(|v| v.repost.is_some()),
+                thread_muted: post
+                    .post
+                    .viewer
+                    .as_ref()
+                    .and_then(|v| v.thread_muted)
+                    .unwrap_or(false),
+                guest: moderation.is_guest(),
             },
+            tags,
+            labels,
+            visibility,
+            date: *post.post.indexed_at.as_ref(),
+        }
+    }
+}
+
+/// Collects the hashtags of a post, both from its facets and the ones stored
+/// outside of the text.
+fn tags(record: &atrium_api::app::bsky::feed::post::RecordData) -> Vec<String> {
+    let mut tags: Vec<String> = record
+        .facets
+        .iter()
+        .flatten()
+        .flat_map(|f| f.features.iter())
+        .filter_map(|f| match f {
+            Union::Refs(MainFeaturesItem::Tag(tag)) => Some(tag.tag.clone()),
+            _ => None,
+        })
+        .collect();
+    tags.extend(record.tags.iter().flatten().cloned());
+
+    let mut unique: Vec<String> = Vec::new();
+    for tag in tags {
+        if !unique.iter().any(|t| t.eq_ignore_ascii_case(&tag)) {
+            unique.push(tag);
+        }
+    }
+
+    unique
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn actor(id: &str) -> Actor {
+        Actor {
+            id: id.to_string(),
+            name: id.to_string(),
+            mutual: false,
+            following: false,
+        }
+    }
+
+    fn notification(reason: &str, actors: &[&str]) -> Notification {
+        Notification {
+            reason: reason.to_string(),
+            actors: actors.iter().map(|a| actor(a)).collect(),
+            post: None,
+            unread: false,
+            date: String::new(),
         }
     }
+
+    #[test]
+    fn query_plain_text() {
+        let query = "rust gemini".parse::<Query>().unwrap();
+
+        assert_eq!(query.text, "rust gemini");
+        assert!(query.is_plain());
+    }
+
+    #[test]
+    fn query_operators() {
+        let query = "from:@alice.test mentions:me since:2024-01-01 until:2024-02-01T00:00:00Z lang:en domain:example.com rust"
+            .parse::<Query>()
+            .unwrap();
+
+        assert_eq!(query.text, "rust");
+        assert_eq!(query.author.as_deref(), Some("alice.test"));
+        assert_eq!(query.mentions.as_deref(), Some("me"));
+        assert_eq!(query.since.as_deref(), Some("2024-01-01"));
+        assert_eq!(query.until.as_deref(), Some("2024-02-01T00:00:00Z"));
+        assert_eq!(query.lang.as_deref(), Some("en"));
+        assert_eq!(query.domain.as_deref(), Some("example.com"));
+        assert!(!query.is_plain());
+    }
+
+    #[test]
+    fn query_unknown_operators_are_text() {
+        let query = "see https://example.com".parse::<Query>().unwrap();
+
+        assert_eq!(query.text, "see https://example.com");
+        assert!(query.is_plain());
+    }
+
+    #[test]
+    fn query_only_operators() {
+        let query = "from:alice.test since:2024-01-01".parse::<Query>().unwrap();
+
+        assert_eq!(query.text, "*");
+        assert_eq!(query.author.as_deref(), Some("alice.test"));
+    }
+
+    #[test]
+    fn query_invalid_operators() {
+        assert!("from:not@valid".parse::<Query>().is_err());
+        assert!("since:yesterday".parse::<Query>().is_err());
+        assert!("lang:not-a-language!".parse::<Query>().is_err());
+    }
+
+    #[test]
+    fn post_options_default() {
+        let options = "".parse::<PostOptions>().unwrap();
+
+        assert_eq!(options.replies, None);
+        assert!(options.quotes);
+        assert_eq!(options.to_string(), "everyone");
+    }
+
+    #[test]
+    fn post_options_rules() {
+        let options = "mentioned following, following list:3kabc noquotes"
+            .parse::<PostOptions>()
+            .unwrap();
+
+        assert_eq!(
+            options.replies,
+            Some(vec![
+                ReplyRule::Mentioned,
+                ReplyRule::Following,
+                ReplyRule::List(String::from("3kabc"))
+            ])
+        );
+        assert!(!options.quotes);
+        assert_eq!(
+            options.to_string(),
+            "mentioned,following,list:3kabc,noquotes"
+        );
+    }
+
+    #[test]
+    fn post_options_nobody() {
+        let options = "nobody".parse::<PostOptions>().unwrap();
+
+        assert_eq!(options.replies, Some(Vec::new()));
+        assert_eq!(options.to_string(), "nobody");
+    }
+
+    #[test]
+    fn post_options_round_trip() {
+        let options = "followers lang:en lang:de label:nudity label:nudity"
+            .parse::<PostOptions>()
+            .unwrap();
+        let parsed = options.to_string().parse::<PostOptions>().unwrap();
+
+        assert_eq!(options.labels, vec![String::from("nudity")]);
+        assert_eq!(parsed.replies, options.replies);
+        assert_eq!(parsed.quotes, options.quotes);
+        assert_eq!(parsed.langs, options.langs);
+        assert_eq!(parsed.labels, options.labels);
+    }
+
+    #[test]
+    fn post_options_invalid() {
+        assert!("anyone".parse::<PostOptions>().is_err());
+        assert!("list:".parse::<PostOptions>().is_err());
+        assert!("label:spam".parse::<PostOptions>().is_err());
+        assert!("lang:en lang:de lang:fr lang:es"
+            .parse::<PostOptions>()
+            .is_err());
+    }
+
+    #[test]
+    fn at_uri_parts() {
+        assert_eq!(
+            at_uri("at://did:plc:abc/app.bsky.feed.post/3kabc"),
+            Some((String::from("did:plc:abc"), String::from("3kabc")))
+        );
+        assert_eq!(at_uri("https://bsky.app/profile/abc"), None);
+        assert_eq!(at_uri("at://did:plc:abc"), None);
+        assert_eq!(at_uri("at://did:plc:abc/app.bsky.feed.post"), None);
+    }
+
+    #[test]
+    fn notification_summary() {
+        assert_eq!(
+            notification("like", &[]).summary(),
+            "Someone liked your post"
+        );
+        assert_eq!(
+            notification("follow", &["a.test"]).summary(),
+            "@a.test followed you"
+        );
+        assert_eq!(
+            notification("repost", &["a.test", "b.test"]).summary(),
+            "@a.test and 1 other reposted your post"
+        );
+        assert_eq!(
+            notification("like", &["a.test", "b.test", "c.test"]).summary(),
+            "@a.test and 2 others liked your post"
+        );
+        assert_eq!(
+            notification("unknown", &["a.test"]).summary(),
+            "@a.test interacted with you"
+        );
+    }
 }
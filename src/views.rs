@@ -1,84 +1,1540 @@
 use crate::{
+    moderation::{self, Visibility},
+    session::Session,
     state::State,
-    types::{Post, Profile},
+    types::{
+        Actor, Convo, Generator, List, ListPurpose, Message, Notification, Post, PostOptions,
+        Profile, ProfileEdit, Query, Search, StarterPack, Word,
+    },
 };
 use askama::Template;
+use atrium_api::types::string::Language;
+use chrono::TimeDelta;
 use fluffer::Fluff;
 use fluskama::FluffTemplate;
+use std::str::FromStr;
 type Client = fluffer::Client<State>;
 
+static OPTIONS_USAGE: &str = "who can reply: \"everyone\", \"nobody\", or any of \"mentioned\", \"followers\", \"following\", \"list:<rkey>\"; add \"noquotes\" to disable quotes, \"lang:<code>\" to set the language, \"label:<sexual|nudity|porn|graphic-media>\" to add a content warning";
+
+static SEARCH_USAGE: &str = "search for posts and accounts; narrow it down with \"from:<handle>\", \"mentions:<handle>\", \"since:<YYYY-MM-DD>\", \"until:<YYYY-MM-DD>\", \"lang:<code>\" or \"domain:<domain>\"";
+
 #[derive(Debug, Template)]
 #[template(path = "feed.gmi", escape = "txt")]
 pub struct Feed {
     session: Option<String>,
+    /// Whether a visitor is browsing in guest mode.
+    guest: bool,
+    /// The number of unread notifications.
+    unread: u64,
+    feeds: Vec<Generator>,
+    tags: Vec<String>,
+    posts: Vec<Post>,
+}
+
+#[derive(Debug, Template)]
+#[template(path = "feeds.gmi", escape = "txt")]
+pub struct FeedsView {
+    session: Option<String>,
+    feeds: Vec<Generator>,
+}
+
+#[derive(Debug, Template)]
+#[template(path = "custom_feed.gmi", escape = "txt")]
+pub struct CustomFeedView {
+    session: Option<String>,
+    /// Whether a visitor is browsing in guest mode.
+    guest: bool,
+    generator: Option<Generator>,
+    posts: Vec<Post>,
+    cursor: Option<String>,
+}
+
+#[derive(Debug, Template)]
+#[template(path = "discover.gmi", escape = "txt")]
+pub struct DiscoverView {
+    session: Option<String>,
+    query: Option<String>,
+    /// Where to return to after saving a feed.
+    back: String,
+    feeds: Vec<Generator>,
+    actors: Vec<Actor>,
+}
+
+#[derive(Debug, Template)]
+#[template(path = "lists.gmi", escape = "txt")]
+pub struct ListsView {
+    session: Option<String>,
+    /// The account whose lists are shown, if it's not the user.
+    profile: Option<String>,
+    lists: Vec<List>,
+    /// The user's own lists, and whether the account is in each of them.
+    memberships: Vec<(List, bool)>,
+}
+
+#[derive(Debug, Template)]
+#[template(path = "list.gmi", escape = "txt")]
+pub struct ListView {
+    session: Option<String>,
+    list: Option<List>,
+    members: Vec<Actor>,
+    posts: Vec<Post>,
+}
+
+#[derive(Debug, Template)]
+#[template(path = "starter_pack.gmi", escape = "txt")]
+pub struct StarterPackView {
+    session: Option<String>,
+    pack: Option<StarterPack>,
+    members: Vec<Actor>,
+    feeds: Vec<Generator>,
+}
+
+#[derive(Debug, Template)]
+#[template(path = "profile.gmi", escape = "txt")]
+pub struct ProfileView {
+    session: Option<String>,
+    /// Whether a visitor is browsing in guest mode.
+    guest: bool,
+    profile: Option<Profile>,
+}
+
+#[derive(Debug, Template)]
+#[template(path = "edit_profile.gmi", escape = "txt")]
+pub struct EditProfileView {
+    session: Option<String>,
+    profile: Option<Profile>,
+    /// Titan URL images are uploaded to, without the image's name.
+    upload: String,
+}
+
+#[derive(Debug, Template)]
+#[template(path = "subscription.gmi", escape = "txt")]
+pub struct SubscriptionView {
+    session: Option<String>,
+    /// Whether a visitor is browsing in guest mode.
+    guest: bool,
+    title: String,
+    /// Path to the page the posts come from, which also prefixes the paths
+    /// of its subscriptions.
+    base: String,
+    posts: Vec<Post>,
+}
+
+#[derive(Debug, Template)]
+#[template(path = "atom.xml")]
+pub struct AtomView {
+    title: String,
+    /// Absolute URL of this server, without a trailing slash.
+    server: String,
+    base: String,
+    updated: String,
+    posts: Vec<Post>,
+}
+
+#[derive(Debug, Template)]
+#[template(path = "post.gmi", escape = "txt")]
+pub struct PostView {
+    session: Option<String>,
+    /// Whether a visitor is browsing in guest mode.
+    guest: bool,
+    post: Option<Post>,
+}
+
+#[derive(Debug, Template)]
+#[template(path = "posts.gmi", escape = "txt")]
+pub struct PostsView {
+    session: Option<String>,
+    title: String,
     posts: Vec<Post>,
+    /// Path to the next page, if any.
+    more: Option<String>,
+}
+
+#[derive(Debug, Template)]
+#[template(path = "moderation.gmi", escape = "txt")]
+pub struct ModerationView {
+    session: Option<String>,
+    labelers: Vec<Actor>,
+}
+
+#[derive(Debug, Template)]
+#[template(path = "actors.gmi", escape = "txt")]
+pub struct ActorsView {
+    session: Option<String>,
+    title: String,
+    actors: Vec<Actor>,
+    /// Path and label of a link shown under each account.
+    action: Option<(String, String)>,
+    /// Path to the next page, if any.
+    more: Option<String>,
+}
+
+#[derive(Debug, Template)]
+#[template(path = "notifications.gmi", escape = "txt")]
+pub struct NotificationsView {
+    session: Option<String>,
+    filter: String,
+    notifications: Vec<Notification>,
+    /// Path to the next page, if any.
+    more: Option<String>,
+}
+
+#[derive(Debug, Template)]
+#[template(path = "convos.gmi", escape = "txt")]
+pub struct ConvosView {
+    session: Option<String>,
+    convos: Vec<Convo>,
+    /// Path to the next page, if any.
+    more: Option<String>,
+}
+
+#[derive(Debug, Template)]
+#[template(path = "convo.gmi", escape = "txt")]
+pub struct ConvoView {
+    session: Option<String>,
+    convo: Option<Convo>,
+    messages: Vec<Message>,
+    /// Path to older messages, if any.
+    older: Option<String>,
+    /// Whether the latest messages are shown.
+    latest: bool,
+}
+
+#[derive(Debug, Template)]
+#[template(path = "words.gmi", escape = "txt")]
+pub struct WordsView {
+    session: Option<String>,
+    words: Vec<Word>,
+}
+
+#[derive(Debug, Template)]
+#[template(path = "search.gmi", escape = "txt")]
+pub struct SearchView {
+    session: Option<String>,
+    query: String,
+    sort: String,
+    saved: bool,
+    search: Option<Search>,
+}
+
+#[derive(Debug, Template)]
+#[template(path = "searches.gmi", escape = "txt")]
+pub struct SearchesView {
+    session: Option<String>,
+    searches: Vec<String>,
+}
+
+#[derive(Debug, Template)]
+#[template(path = "tag.gmi", escape = "txt")]
+pub struct TagView {
+    session: Option<String>,
+    /// Whether a visitor is browsing in guest mode.
+    guest: bool,
+    tag: String,
+    sort: String,
+    followed: bool,
+    search: Option<Search>,
+}
+
+pub async fn feed(c: Client) -> FluffTemplate<Feed> {
+    if let Some((session, Some(handle))) = reader(&c) {
+        let feed = session.clone().feed().await.unwrap();
+        let feeds = session.clone().feeds().await.unwrap();
+        // The badge isn't essential to the page, so failing to get it
+        // shouldn't break it.
+        let unread = session.clone().unread_notifications().await.unwrap_or(0);
+
+        FluffTemplate::from(Feed {
+            session: Some(handle),
+            guest: false,
+            unread,
+            feeds: feeds.into_iter().filter(|f| f.pinned).collect(),
+            tags: session.clone().tags().await,
+            posts: feed,
+        })
+    } else {
+        FluffTemplate::from(Feed {
+            session: None,
+            guest: c.state.guest.is_some(),
+            unread: 0,
+            feeds: Vec::new(),
+            tags: Vec::new(),
+            posts: Vec::new(),
+        })
+    }
+}
+
+pub async fn feeds(c: Client) -> FluffTemplate<FeedsView> {
+    if let Some(fingerprint) = c.fingerprint() {
+        let session = c.state.sessions.get(&fingerprint).unwrap();
+        let feeds = session.clone().feeds().await.unwrap();
+
+        FluffTemplate::from(FeedsView {
+            session: Some(session.handle.clone()),
+            feeds,
+        })
+    } else {
+        FluffTemplate::from(FeedsView {
+            session: None,
+            feeds: Vec::new(),
+        })
+    }
+}
+
+pub async fn custom_feed(c: Client) -> FluffTemplate<CustomFeedView> {
+    if let Some((session, handle)) = reader(&c) {
+        let did = c.parameter("did").unwrap();
+        let rkey = c.parameter("rkey").unwrap();
+        let cursor = c.input();
+        let (generator, posts, cursor) = session.custom_feed(did, rkey, cursor).await.unwrap();
+
+        FluffTemplate::from(CustomFeedView {
+            guest: handle.is_none(),
+            session: handle,
+            generator: Some(generator),
+            posts,
+            cursor,
+        })
+    } else {
+        FluffTemplate::from(CustomFeedView {
+            session: None,
+            guest: false,
+            generator: None,
+            posts: Vec::new(),
+            cursor: None,
+        })
+    }
+}
+
+pub async fn discover(c: Client) -> FluffTemplate<DiscoverView> {
+    if let Some(fingerprint) = c.fingerprint() {
+        let session = c.state.sessions.get(&fingerprint).unwrap();
+        let feeds = session.clone().suggested_feeds().await.unwrap();
+        let actors = session.clone().suggested_accounts().await.unwrap();
+
+        FluffTemplate::from(DiscoverView {
+            session: Some(session.handle.clone()),
+            query: None,
+            back: String::from("/d"),
+            feeds,
+            actors,
+        })
+    } else {
+        FluffTemplate::from(DiscoverView {
+            session: None,
+            query: None,
+            back: String::from("/d"),
+            feeds: Vec::new(),
+            actors: Vec::new(),
+        })
+    }
+}
+
+pub async fn search_feeds(c: Client) -> Result<FluffTemplate<DiscoverView>, Fluff> {
+    let Some(query) = c.input() else {
+        return Err(Fluff::Input(String::from("search for feeds")));
+    };
+    let back = format!("/d/s?{}", urlencoding::encode(&query));
+
+    Ok(if let Some(fingerprint) = c.fingerprint() {
+        let session = c.state.sessions.get(&fingerprint).unwrap();
+        let feeds = session.clone().popular_feeds(Some(&query)).await.unwrap();
+
+        FluffTemplate::from(DiscoverView {
+            session: Some(session.handle.clone()),
+            query: Some(query),
+            back,
+            feeds,
+            actors: Vec::new(),
+        })
+    } else {
+        FluffTemplate::from(DiscoverView {
+            session: None,
+            query: Some(query),
+            back,
+            feeds: Vec::new(),
+            actors: Vec::new(),
+        })
+    })
+}
+
+pub async fn open_feed(c: Client) -> Fluff {
+    let Some(input) = c.input() else {
+        return Fluff::Input(String::from("feed URI or bsky.app link"));
+    };
+
+    if let Some(fingerprint) = c.fingerprint() {
+        let session = c.state.sessions.get(&fingerprint).unwrap().clone();
+
+        match session.resolve_link(input.trim(), "feed").await {
+            Ok((did, rkey)) => Fluff::RedirectTemporary(format!("/f/{did}/{rkey}")),
+            Err(e) => Fluff::Input(format!("{e}; feed URI or bsky.app link")),
+        }
+    } else {
+        Fluff::RedirectTemporary(String::from("/f"))
+    }
+}
+
+pub async fn save_feed(c: Client) -> Fluff {
+    let did = c.parameter("did").unwrap();
+    let rkey = c.parameter("rkey").unwrap();
+
+    if let Some(fingerprint) = c.fingerprint() {
+        c.state
+            .clone()
+            .sessions
+            .get(&fingerprint)
+            .unwrap()
+            .clone()
+            .save_feed(did, rkey)
+            .await
+            .unwrap();
+    }
+
+    back(&c, format!("/f/{did}/{rkey}"))
+}
+
+pub async fn pin_feed(c: Client) -> Fluff {
+    let did = c.parameter("did").unwrap();
+    let rkey = c.parameter("rkey").unwrap();
+
+    if let Some(fingerprint) = c.fingerprint() {
+        c.state
+            .clone()
+            .sessions
+            .get(&fingerprint)
+            .unwrap()
+            .clone()
+            .pin_feed(did, rkey)
+            .await
+            .unwrap();
+    }
+
+    Fluff::RedirectTemporary(format!("/f/{did}/{rkey}"))
+}
+
+pub async fn like_feed(c: Client) -> Fluff {
+    let did = c.parameter("did").unwrap();
+    let rkey = c.parameter("rkey").unwrap();
+
+    if let Some(fingerprint) = c.fingerprint() {
+        c.state
+            .clone()
+            .sessions
+            .get(&fingerprint)
+            .unwrap()
+            .clone()
+            .like_feed(did, rkey)
+            .await
+            .unwrap();
+    }
+
+    Fluff::RedirectTemporary(format!("/f/{did}/{rkey}"))
+}
+
+pub async fn lists(c: Client) -> FluffTemplate<ListsView> {
+    let profile = c.parameter("profile").map(String::from);
+
+    if let Some(fingerprint) = c.fingerprint() {
+        let session = c.state.sessions.get(&fingerprint).unwrap();
+        let (lists, memberships) = match &profile {
+            Some(id) => (
+                session.clone().lists(id).await.unwrap(),
+                session.clone().memberships(id).await.unwrap(),
+            ),
+            None => (
+                session.clone().lists(&session.handle).await.unwrap(),
+                Vec::new(),
+            ),
+        };
+
+        FluffTemplate::from(ListsView {
+            session: Some(session.handle.clone()),
+            profile,
+            lists,
+            memberships,
+        })
+    } else {
+        FluffTemplate::from(ListsView {
+            session: None,
+            profile,
+            lists: Vec::new(),
+            memberships: Vec::new(),
+        })
+    }
+}
+
+pub async fn list(c: Client) -> FluffTemplate<ListView> {
+    if let Some(fingerprint) = c.fingerprint() {
+        let did = c.parameter("did").unwrap();
+        let rkey = c.parameter("rkey").unwrap();
+        let session = c.state.sessions.get(&fingerprint).unwrap();
+        let (list, members, posts) = session.clone().list(did, rkey).await.unwrap();
+
+        FluffTemplate::from(ListView {
+            session: Some(session.handle.clone()),
+            list: Some(list),
+            members,
+            posts,
+        })
+    } else {
+        FluffTemplate::from(ListView {
+            session: None,
+            list: None,
+            members: Vec::new(),
+            posts: Vec::new(),
+        })
+    }
+}
+
+pub async fn open_list(c: Client) -> Fluff {
+    let Some(input) = c.input() else {
+        return Fluff::Input(String::from("list URI or bsky.app link"));
+    };
+
+    if let Some(fingerprint) = c.fingerprint() {
+        let session = c.state.sessions.get(&fingerprint).unwrap().clone();
+
+        match session.resolve_link(input.trim(), "lists").await {
+            Ok((did, rkey)) => Fluff::RedirectTemporary(format!("/ls/{did}/{rkey}")),
+            Err(e) => Fluff::Input(format!("{e}; list URI or bsky.app link")),
+        }
+    } else {
+        Fluff::RedirectTemporary(String::from("/ls"))
+    }
+}
+
+pub async fn new_list(c: Client) -> Fluff {
+    let Some(purpose) = c.parameter("purpose") else {
+        return match c.input().as_deref().map(str::trim) {
+            Some(p @ ("curate" | "mod")) => Fluff::RedirectTemporary(format!("/ls/n/{p}")),
+            _ => Fluff::Input(String::from(
+                "list type: \"curate\" for a list of accounts, or \"mod\" for a moderation list",
+            )),
+        };
+    };
+    let purpose = match purpose {
+        "curate" => ListPurpose::Curation,
+        "mod" => ListPurpose::Moderation,
+        _ => return Fluff::NotFound(String::from("Unknown list type.")),
+    };
+
+    let Some(input) = c.input() else {
+        return Fluff::Input(String::from("list name; optional description"));
+    };
+    let (name, description) = match input.split_once(';') {
+        Some((name, description)) => (name.trim(), Some(description.trim())),
+        None => (input.trim(), None),
+    };
+    if name.is_empty() || name.chars().count() > 64 {
+        return Fluff::Input(String::from(
+            "the name must be 1 to 64 characters long; list name; optional description",
+        ));
+    }
+
+    if let Some(fingerprint) = c.fingerprint() {
+        let session = c.state.sessions.get(&fingerprint).unwrap().clone();
+        let (did, rkey) = session
+            .create_list(name, description.filter(|d| !d.is_empty()), purpose)
+            .await
+            .unwrap();
+
+        Fluff::RedirectTemporary(format!("/ls/{did}/{rkey}"))
+    } else {
+        Fluff::RedirectTemporary(String::from("/ls"))
+    }
+}
+
+pub async fn delete_list(c: Client) -> Fluff {
+    let did = c.parameter("did").unwrap();
+    let rkey = c.parameter("rkey").unwrap();
+
+    match c.input().as_deref() {
+        Some("yes") => (),
+        Some(_) => return Fluff::RedirectTemporary(format!("/ls/{did}/{rkey}")),
+        None => {
+            return Fluff::Input(String::from(
+                "type \"yes\" to delete this list and everyone on it",
+            ))
+        }
+    }
+
+    if let Some(fingerprint) = c.fingerprint() {
+        let session = c.state.clone().sessions.get(&fingerprint).unwrap().clone();
+        if let Err(e) = session.delete_list(did, rkey).await {
+            return Fluff::FailurePermanent(e.to_string());
+        }
+    }
+
+    Fluff::RedirectTemporary(String::from("/ls"))
+}
+
+pub async fn mute_list(c: Client) -> Fluff {
+    let did = c.parameter("did").unwrap();
+    let rkey = c.parameter("rkey").unwrap();
+
+    if let Some(fingerprint) = c.fingerprint() {
+        c.state
+            .clone()
+            .sessions
+            .get(&fingerprint)
+            .unwrap()
+            .clone()
+            .mute_list(did, rkey)
+            .await
+            .unwrap();
+    }
+
+    Fluff::RedirectTemporary(format!("/ls/{did}/{rkey}"))
+}
+
+pub async fn block_list(c: Client) -> Fluff {
+    let did = c.parameter("did").unwrap();
+    let rkey = c.parameter("rkey").unwrap();
+
+    if let Some(fingerprint) = c.fingerprint() {
+        c.state
+            .clone()
+            .sessions
+            .get(&fingerprint)
+            .unwrap()
+            .clone()
+            .block_list(did, rkey)
+            .await
+            .unwrap();
+    }
+
+    Fluff::RedirectTemporary(format!("/ls/{did}/{rkey}"))
+}
+
+pub async fn list_member(c: Client) -> Fluff {
+    let profile = c.parameter("profile").unwrap();
+    let rkey = c.parameter("rkey").unwrap();
+
+    if let Some(fingerprint) = c.fingerprint() {
+        c.state
+            .clone()
+            .sessions
+            .get(&fingerprint)
+            .unwrap()
+            .clone()
+            .toggle_member(profile, rkey)
+            .await
+            .unwrap();
+    }
+
+    back(&c, format!("/@{profile}/l"))
+}
+
+pub async fn starter_pack(c: Client) -> FluffTemplate<StarterPackView> {
+    if let Some(fingerprint) = c.fingerprint() {
+        let did = c.parameter("did").unwrap();
+        let rkey = c.parameter("rkey").unwrap();
+        let session = c.state.sessions.get(&fingerprint).unwrap();
+        let (pack, members, feeds) = session.clone().starter_pack(did, rkey).await.unwrap();
+
+        FluffTemplate::from(StarterPackView {
+            session: Some(session.handle.clone()),
+            pack: Some(pack),
+            members,
+            feeds,
+        })
+    } else {
+        FluffTemplate::from(StarterPackView {
+            session: None,
+            pack: None,
+            members: Vec::new(),
+            feeds: Vec::new(),
+        })
+    }
+}
+
+pub async fn follow_all(c: Client) -> Fluff {
+    let did = c.parameter("did").unwrap();
+    let rkey = c.parameter("rkey").unwrap();
+
+    if let Some(fingerprint) = c.fingerprint() {
+        c.state
+            .clone()
+            .sessions
+            .get(&fingerprint)
+            .unwrap()
+            .clone()
+            .follow_all(did, rkey)
+            .await
+            .unwrap();
+    }
+
+    Fluff::RedirectTemporary(format!("/sp/{did}/{rkey}"))
+}
+
+pub async fn new_starter_pack(c: Client) -> Fluff {
+    let rkey = c.parameter("rkey").unwrap();
+    let Some(input) = c.input() else {
+        return Fluff::Input(String::from("starter pack name; optional description"));
+    };
+    let (name, description) = match input.split_once(';') {
+        Some((name, description)) => (name.trim(), Some(description.trim())),
+        None => (input.trim(), None),
+    };
+    if name.is_empty() || name.chars().count() > 50 {
+        return Fluff::Input(String::from(
+            "the name must be 1 to 50 characters long; starter pack name; optional description",
+        ));
+    }
+
+    if let Some(fingerprint) = c.fingerprint() {
+        let session = c.state.sessions.get(&fingerprint).unwrap().clone();
+        let (did, rkey) = session
+            .create_starter_pack(rkey, name, description.filter(|d| !d.is_empty()))
+            .await
+            .unwrap();
+
+        Fluff::RedirectTemporary(format!("/sp/{did}/{rkey}"))
+    } else {
+        Fluff::RedirectTemporary(String::from("/ls"))
+    }
+}
+
+pub async fn connections(c: Client) -> Result<FluffTemplate<ActorsView>, Fluff> {
+    let profile = c.parameter("profile").unwrap();
+    let kind = c.parameter("kind").unwrap();
+    let title = match kind {
+        "followers" => format!("Followers of @{profile}"),
+        "following" => format!("Followed by @{profile}"),
+        "known" => format!("Followers of @{profile} you know"),
+        _ => return Err(Fluff::NotFound(String::from("Unknown listing."))),
+    };
+
+    Ok(if let Some(fingerprint) = c.fingerprint() {
+        let session = c.state.sessions.get(&fingerprint).unwrap().clone();
+        let cursor = c.input();
+        let (actors, cursor) = match kind {
+            "followers" => session.clone().followers(profile, cursor).await,
+            "following" => session.clone().follows(profile, cursor).await,
+            _ => session.clone().known_followers(profile, cursor).await,
+        }
+        .unwrap();
+
+        FluffTemplate::from(ActorsView {
+            session: Some(session.handle.clone()),
+            title,
+            actors,
+            action: None,
+            more: cursor.map(|c| format!("/@{profile}/{kind}?{}", urlencoding::encode(&c))),
+        })
+    } else {
+        FluffTemplate::from(ActorsView {
+            session: None,
+            title,
+            actors: Vec::new(),
+            action: None,
+            more: None,
+        })
+    })
+}
+
+pub async fn profile(c: Client) -> FluffTemplate<ProfileView> {
+    if let Some((session, handle)) = reader(&c) {
+        let parameter = c.parameter("profile").unwrap();
+        let guest = handle.is_none();
+        let profile = session.profile(parameter).await.unwrap();
+
+        FluffTemplate::from(ProfileView {
+            session: handle,
+            guest,
+            // Accounts can ask to be kept from visitors who aren't signed in.
+            profile: Some(profile).filter(|p| !guest || p.visibility != Visibility::Hide),
+        })
+    } else {
+        FluffTemplate::from(ProfileView {
+            session: None,
+            guest: false,
+            profile: None,
+        })
+    }
+}
+
+pub async fn edit_profile(c: Client) -> FluffTemplate<EditProfileView> {
+    let upload = server_url(&c, "titan", "/e");
+
+    if let Some(fingerprint) = c.fingerprint() {
+        let session = c.state.sessions.get(&fingerprint).unwrap();
+        let profile = session.clone().profile(&session.handle).await.unwrap();
+
+        FluffTemplate::from(EditProfileView {
+            session: Some(session.handle.clone()),
+            profile: Some(profile),
+            upload,
+        })
+    } else {
+        FluffTemplate::from(EditProfileView {
+            session: None,
+            profile: None,
+            upload,
+        })
+    }
+}
+
+pub async fn edit_name(c: Client) -> Fluff {
+    if let Some(fingerprint) = c.fingerprint() {
+        let Some(input) = c.input() else {
+            return Fluff::Input(String::from("display name; leave empty to remove it"));
+        };
+        let name = input.trim();
+        if name.chars().count() > 64 {
+            return Fluff::Input(String::from(
+                "the name can be up to 64 characters long; display name",
+            ));
+        }
+
+        let session = c.state.sessions.get(&fingerprint).unwrap().clone();
+        session
+            .edit_profile(ProfileEdit::Name(name.to_string()))
+            .await
+            .unwrap();
+    }
+
+    Fluff::RedirectTemporary(String::from("/e"))
+}
+
+pub async fn edit_bio(c: Client) -> Fluff {
+    if let Some(fingerprint) = c.fingerprint() {
+        let Some(input) = c.input() else {
+            return Fluff::Input(String::from("description; leave empty to remove it"));
+        };
+        let bio = input.trim();
+        if bio.chars().count() > 256 {
+            return Fluff::Input(String::from(
+                "the description can be up to 256 characters long; description",
+            ));
+        }
+
+        let session = c.state.sessions.get(&fingerprint).unwrap().clone();
+        session
+            .edit_profile(ProfileEdit::Bio(bio.to_string()))
+            .await
+            .unwrap();
+    }
+
+    Fluff::RedirectTemporary(String::from("/e"))
+}
+
+/// Takes an avatar or banner uploaded through Titan. Redirects have to be
+/// absolute, since relative ones would point back at the Titan URL.
+pub async fn upload_image(c: Client) -> Fluff {
+    let done = server_url(&c, "gemini", "/e");
+    let (Some(fingerprint), Some(upload)) = (c.fingerprint(), &c.titan) else {
+        return Fluff::RedirectTemporary(done);
+    };
+    if !matches!(upload.mime.as_str(), "image/png" | "image/jpeg") {
+        return Fluff::FailurePermanent(String::from("Only PNG and JPEG images are supported."));
+    }
+
+    let edit = match c.parameter("image") {
+        Some("avatar") => ProfileEdit::Avatar(upload.content.clone()),
+        Some("banner") => ProfileEdit::Banner(upload.content.clone()),
+        _ => return Fluff::NotFound(String::from("Unknown image.")),
+    };
+    let session = c.state.sessions.get(&fingerprint).unwrap().clone();
+    session.edit_profile(edit).await.unwrap();
+
+    Fluff::RedirectTemporary(done)
+}
+
+pub async fn show(c: Client) -> FluffTemplate<PostView> {
+    if let Some((session, handle)) = reader(&c) {
+        let id = c.parameter("id").unwrap();
+        let post = session.view(id).await.ok();
+
+        FluffTemplate::from(PostView {
+            guest: handle.is_none(),
+            session: handle,
+            post,
+        })
+    } else {
+        FluffTemplate::from(PostView {
+            session: None,
+            guest: false,
+            post: None,
+        })
+    }
+}
+
+pub async fn permalink(c: Client) -> FluffTemplate<PostView> {
+    if let Some((session, handle)) = reader(&c) {
+        let profile = c.parameter("profile").unwrap();
+        let rkey = c.parameter("rkey").unwrap();
+        let post = session.permalink(profile, rkey).await.ok();
+
+        FluffTemplate::from(PostView {
+            guest: handle.is_none(),
+            session: handle,
+            post,
+        })
+    } else {
+        FluffTemplate::from(PostView {
+            session: None,
+            guest: false,
+            post: None,
+        })
+    }
+}
+
+/// Lists posts as a Gemini subscription page, which feed readers such as
+/// Lagrange can follow.
+pub async fn gemsub(c: Client) -> FluffTemplate<SubscriptionView> {
+    if let Some((session, handle)) = subscriber(&c) {
+        let (title, base, posts) = subscription(&c, session).await;
+
+        FluffTemplate::from(SubscriptionView {
+            guest: handle.is_none(),
+            session: handle,
+            title,
+            base,
+            posts,
+        })
+    } else {
+        FluffTemplate::from(SubscriptionView {
+            session: None,
+            guest: false,
+            title: String::new(),
+            base: String::new(),
+            posts: Vec::new(),
+        })
+    }
+}
+
+/// Same as [`gemsub`], as an Atom feed.
+pub async fn atom(c: Client) -> Fluff {
+    let Some((session, _)) = subscriber(&c) else {
+        return Fluff::FailurePermanent(String::from(
+            "You need to sign in using a registered client certificate.",
+        ));
+    };
+
+    let (title, base, posts) = subscription(&c, session).await;
+    let updated = posts
+        .iter()
+        .map(|p| p.date)
+        .max()
+        .map_or_else(|| chrono::Utc::now().to_rfc3339(), |d| d.to_rfc3339());
+    let feed = AtomView {
+        title,
+        server: server_url(&c, "gemini", ""),
+        base,
+        updated,
+        posts,
+    };
+
+    match feed.render() {
+        Ok(body) => Fluff::Document {
+            mime: String::from("application/atom+xml"),
+            body,
+        },
+        Err(e) => Fluff::FailurePermanent(e.to_string()),
+    }
+}
+
+pub async fn engagement(c: Client) -> Result<FluffTemplate<ActorsView>, Fluff> {
+    let profile = c.parameter("profile").unwrap();
+    let rkey = c.parameter("rkey").unwrap();
+    let kind = c.parameter("kind").unwrap();
+    let title = match kind {
+        "likes" => String::from("Liked by"),
+        "reposts" => String::from("Reposted by"),
+        _ => return Err(Fluff::NotFound(String::from("Unknown listing."))),
+    };
+
+    Ok(if let Some(fingerprint) = c.fingerprint() {
+        let session = c.state.sessions.get(&fingerprint).unwrap().clone();
+        let cursor = c.input();
+        let (actors, cursor) = match kind {
+            "likes" => session.clone().likes(profile, rkey, cursor).await,
+            _ => session.clone().reposted_by(profile, rkey, cursor).await,
+        }
+        .map_err(|_| Fluff::NotFound(String::from("Post not found.")))?;
+
+        FluffTemplate::from(ActorsView {
+            session: Some(session.handle.clone()),
+            title,
+            actors,
+            action: None,
+            more: cursor
+                .map(|c| format!("/@{profile}/p/{rkey}/{kind}?{}", urlencoding::encode(&c))),
+        })
+    } else {
+        FluffTemplate::from(ActorsView {
+            session: None,
+            title,
+            actors: Vec::new(),
+            action: None,
+            more: None,
+        })
+    })
+}
+
+pub async fn quotes(c: Client) -> Result<FluffTemplate<PostsView>, Fluff> {
+    let profile = c.parameter("profile").unwrap();
+    let rkey = c.parameter("rkey").unwrap();
+    let title = String::from("Quotes");
+
+    Ok(if let Some(fingerprint) = c.fingerprint() {
+        let session = c.state.sessions.get(&fingerprint).unwrap().clone();
+        let cursor = c.input();
+        let (posts, cursor) = session
+            .clone()
+            .quotes(profile, rkey, cursor)
+            .await
+            .map_err(|_| Fluff::NotFound(String::from("Post not found.")))?;
+
+        FluffTemplate::from(PostsView {
+            session: Some(session.handle.clone()),
+            title,
+            posts,
+            more: cursor
+                .map(|c| format!("/@{profile}/p/{rkey}/quotes?{}", urlencoding::encode(&c))),
+        })
+    } else {
+        FluffTemplate::from(PostsView {
+            session: None,
+            title,
+            posts: Vec::new(),
+            more: None,
+        })
+    })
+}
+
+pub async fn search(c: Client) -> Result<FluffTemplate<SearchView>, Fluff> {
+    // Further pages carry the cursor along with the query, as opaque cursors
+    // can't be trusted in the path.
+    let (input, cursor) = if c.url.path().ends_with("/more") {
+        (c.query("q"), c.query("cursor"))
+    } else {
+        (c.input(), None)
+    };
+    let Some(input) = input else {
+        return Err(Fluff::Input(SEARCH_USAGE.to_string()));
+    };
+    let sort = c.parameter("sort").unwrap_or("top");
+    if sort != "top" && sort != "latest" {
+        return Err(Fluff::NotFound(String::from("Unknown sort order.")));
+    }
+    let query = match input.parse::<Query>() {
+        Ok(query) => query,
+        Err(e) => return Err(Fluff::Input(format!("{e}; {SEARCH_USAGE}"))),
+    };
+
+    Ok(if let Some(fingerprint) = c.fingerprint() {
+        let session = c.state.sessions.get(&fingerprint).unwrap();
+        let saved = session.clone().searches().await.contains(&input);
+        let search = session.clone().search(&query, sort, cursor).await.unwrap();
+
+        FluffTemplate::from(SearchView {
+            session: Some(session.handle.clone()),
+            query: input,
+            sort: sort.to_string(),
+            saved,
+            search: Some(search),
+        })
+    } else {
+        FluffTemplate::from(SearchView {
+            session: None,
+            query: input,
+            sort: sort.to_string(),
+            saved: false,
+            search: None,
+        })
+    })
+}
+
+pub async fn searches(c: Client) -> FluffTemplate<SearchesView> {
+    if let Some(fingerprint) = c.fingerprint() {
+        let session = c.state.sessions.get(&fingerprint).unwrap();
+
+        FluffTemplate::from(SearchesView {
+            session: Some(session.handle.clone()),
+            searches: session.clone().searches().await,
+        })
+    } else {
+        FluffTemplate::from(SearchesView {
+            session: None,
+            searches: Vec::new(),
+        })
+    }
+}
+
+pub async fn save_search(c: Client) -> Fluff {
+    let Some(query) = c.input() else {
+        return Fluff::Input(String::from("search to save"));
+    };
+
+    if let Some(fingerprint) = c.fingerprint() {
+        c.state
+            .clone()
+            .sessions
+            .get(&fingerprint)
+            .unwrap()
+            .clone()
+            .save_search(&query)
+            .await
+            .unwrap();
+    }
+
+    Fluff::RedirectTemporary(String::from("/s/saved"))
+}
+
+pub async fn tag(c: Client) -> Result<FluffTemplate<TagView>, Fluff> {
+    let Some(tag) = tag_name(c.parameter("tag").unwrap()) else {
+        return Err(Fluff::NotFound(String::from("Invalid hashtag.")));
+    };
+    let sort = c.parameter("sort").unwrap_or("latest");
+    if sort != "top" && sort != "latest" {
+        return Err(Fluff::NotFound(String::from("Unknown sort order.")));
+    }
+
+    Ok(if let Some((session, handle)) = reader(&c) {
+        let cursor = c.input();
+        let followed = session
+            .clone()
+            .tags()
+            .await
+            .iter()
+            .any(|t| t.eq_ignore_ascii_case(&tag));
+        let search = session.tag(&tag, sort, cursor).await.unwrap();
+
+        FluffTemplate::from(TagView {
+            guest: handle.is_none(),
+            session: handle,
+            tag,
+            sort: sort.to_string(),
+            followed,
+            search: Some(search),
+        })
+    } else {
+        FluffTemplate::from(TagView {
+            session: None,
+            guest: false,
+            tag,
+            sort: sort.to_string(),
+            followed: false,
+            search: None,
+        })
+    })
+}
+
+pub async fn follow_tag(c: Client) -> Fluff {
+    let Some(tag) = tag_name(c.parameter("tag").unwrap()) else {
+        return Fluff::NotFound(String::from("Invalid hashtag."));
+    };
+
+    if let Some(fingerprint) = c.fingerprint() {
+        c.state
+            .clone()
+            .sessions
+            .get(&fingerprint)
+            .unwrap()
+            .clone()
+            .follow_tag(&tag)
+            .await
+            .unwrap();
+    }
+
+    Fluff::RedirectTemporary(format!("/t/{}", urlencoding::encode(&tag)))
+}
+
+pub async fn follow(c: Client) -> Fluff {
+    let profile = c.parameter("profile").unwrap();
+
+    if let Some(fingerprint) = c.fingerprint() {
+        c.state
+            .clone()
+            .sessions
+            .get(&fingerprint)
+            .unwrap()
+            .clone()
+            .follow(profile)
+            .await
+            .unwrap();
+    }
+
+    back(&c, format!("/@{}", profile))
+}
+
+pub async fn subscribe(c: Client) -> Fluff {
+    let profile = c.parameter("profile").unwrap();
+
+    if let Some(fingerprint) = c.fingerprint() {
+        c.state
+            .clone()
+            .sessions
+            .get(&fingerprint)
+            .unwrap()
+            .clone()
+            .subscribe(profile)
+            .await
+            .unwrap();
+    }
+
+    Fluff::RedirectTemporary(format!("/@{}", profile))
 }
 
-#[derive(Debug, Template)]
-#[template(path = "profile.gmi", escape = "txt")]
-pub struct ProfileView {
-    session: Option<String>,
-    profile: Option<Profile>,
+pub async fn mute(c: Client) -> Fluff {
+    let profile = c.parameter("profile").unwrap();
+
+    if let Some(fingerprint) = c.fingerprint() {
+        c.state
+            .clone()
+            .sessions
+            .get(&fingerprint)
+            .unwrap()
+            .clone()
+            .mute(profile)
+            .await
+            .unwrap();
+    }
+
+    Fluff::RedirectTemporary(format!("/@{}", profile))
+}
+
+pub async fn block(c: Client) -> Fluff {
+    let profile = c.parameter("profile").unwrap();
+
+    if let Some(fingerprint) = c.fingerprint() {
+        c.state
+            .clone()
+            .sessions
+            .get(&fingerprint)
+            .unwrap()
+            .clone()
+            .block(profile)
+            .await
+            .unwrap();
+    }
+
+    Fluff::RedirectTemporary(format!("/@{}", profile))
+}
+
+pub async fn notifications(c: Client) -> Result<FluffTemplate<NotificationsView>, Fluff> {
+    let filter = c.parameter("filter").unwrap_or("all");
+    let reasons: &[&str] = match filter {
+        "all" => &[],
+        "mentions" => &["mention"],
+        "replies" => &["reply"],
+        _ => return Err(Fluff::NotFound(String::from("Unknown filter."))),
+    };
+
+    Ok(if let Some(fingerprint) = c.fingerprint() {
+        let session = c.state.sessions.get(&fingerprint).unwrap().clone();
+        let cursor = c.input();
+        let (notifications, cursor) = session
+            .clone()
+            .notifications(reasons, cursor)
+            .await
+            .unwrap();
+
+        FluffTemplate::from(NotificationsView {
+            session: Some(session.handle.clone()),
+            filter: filter.to_string(),
+            notifications,
+            more: cursor.map(|c| format!("/n/{filter}?{}", urlencoding::encode(&c))),
+        })
+    } else {
+        FluffTemplate::from(NotificationsView {
+            session: None,
+            filter: filter.to_string(),
+            notifications: Vec::new(),
+            more: None,
+        })
+    })
+}
+
+pub async fn convos(c: Client) -> FluffTemplate<ConvosView> {
+    if let Some(fingerprint) = c.fingerprint() {
+        let session = c.state.sessions.get(&fingerprint).unwrap().clone();
+        let cursor = c.input();
+        let (convos, cursor) = session.clone().convos(cursor).await.unwrap();
+
+        FluffTemplate::from(ConvosView {
+            session: Some(session.handle.clone()),
+            convos,
+            more: cursor.map(|c| format!("/dm?{}", urlencoding::encode(&c))),
+        })
+    } else {
+        FluffTemplate::from(ConvosView {
+            session: None,
+            convos: Vec::new(),
+            more: None,
+        })
+    }
+}
+
+pub async fn convo(c: Client) -> FluffTemplate<ConvoView> {
+    if let Some(fingerprint) = c.fingerprint() {
+        let id = c.parameter("convo").unwrap();
+        let session = c.state.sessions.get(&fingerprint).unwrap().clone();
+        let cursor = c.input();
+        let latest = cursor.is_none();
+        let (convo, messages, older) = match session.clone().convo(id, cursor).await {
+            Ok((convo, messages, older)) => (Some(convo), messages, older),
+            Err(_) => (None, Vec::new(), None),
+        };
+
+        FluffTemplate::from(ConvoView {
+            session: Some(session.handle.clone()),
+            convo,
+            messages,
+            older: older.map(|c| format!("/dm/c/{id}?{}", urlencoding::encode(&c))),
+            latest,
+        })
+    } else {
+        FluffTemplate::from(ConvoView {
+            session: None,
+            convo: None,
+            messages: Vec::new(),
+            older: None,
+            latest: true,
+        })
+    }
+}
+
+pub async fn send_message(c: Client) -> Fluff {
+    let id = c.parameter("convo").unwrap();
+
+    if let Some(fingerprint) = c.fingerprint() {
+        let Some(input) = c.input() else {
+            return Fluff::Input(String::from("message"));
+        };
+        let text = input.trim();
+        if text.is_empty() || text.chars().count() > 1000 {
+            return Fluff::Input(String::from(
+                "messages must be 1 to 1000 characters long; message",
+            ));
+        }
+
+        let session = c.state.sessions.get(&fingerprint).unwrap().clone();
+        session.send_message(id, text).await.unwrap();
+    }
+
+    Fluff::RedirectTemporary(format!("/dm/c/{id}"))
+}
+
+pub async fn mark_read(c: Client) -> Fluff {
+    let id = c.parameter("convo").unwrap();
+
+    if let Some(fingerprint) = c.fingerprint() {
+        let session = c.state.sessions.get(&fingerprint).unwrap().clone();
+        session.mark_read(id).await.unwrap();
+    }
+
+    back(&c, format!("/dm/c/{id}"))
+}
+
+pub async fn message(c: Client) -> Fluff {
+    let profile = c.parameter("profile").unwrap();
+
+    if let Some(fingerprint) = c.fingerprint() {
+        let session = c.state.sessions.get(&fingerprint).unwrap().clone();
+
+        match session.start_convo(profile).await {
+            Ok(id) => Fluff::RedirectTemporary(format!("/dm/c/{id}")),
+            Err(e) => Fluff::FailurePermanent(format!("Can't message @{profile}: {e}")),
+        }
+    } else {
+        Fluff::RedirectTemporary(format!("/@{profile}"))
+    }
 }
 
-pub async fn feed<'a>(c: Client) -> FluffTemplate<Feed> {
+pub async fn muted(c: Client) -> FluffTemplate<ActorsView> {
+    let action = Some((String::from("m"), String::from("🔊 Unmute")));
+
     if let Some(fingerprint) = c.fingerprint() {
         let session = c.state.sessions.get(&fingerprint).unwrap();
-        let feed = session.clone().feed().await.unwrap();
+        let actors = session.clone().muted().await.unwrap();
 
-        FluffTemplate::from(Feed {
+        FluffTemplate::from(ActorsView {
             session: Some(session.handle.clone()),
-            posts: feed,
+            title: String::from("Muted Accounts"),
+            actors,
+            action,
+            more: None,
         })
     } else {
-        FluffTemplate::from(Feed {
+        FluffTemplate::from(ActorsView {
             session: None,
-            posts: Vec::new(),
+            title: String::from("Muted Accounts"),
+            actors: Vec::new(),
+            action,
+            more: None,
         })
     }
 }
 
-pub async fn profile<'a>(c: Client) -> FluffTemplate<ProfileView> {
+pub async fn blocked(c: Client) -> FluffTemplate<ActorsView> {
+    let action = Some((String::from("b"), String::from("✅ Unblock")));
+
     if let Some(fingerprint) = c.fingerprint() {
-        let parameter = c.parameter("profile").unwrap();
         let session = c.state.sessions.get(&fingerprint).unwrap();
-        let profile = session.clone().profile(parameter).await.unwrap();
+        let actors = session.clone().blocked().await.unwrap();
 
-        FluffTemplate::from(ProfileView {
+        FluffTemplate::from(ActorsView {
             session: Some(session.handle.clone()),
-            profile: Some(profile),
+            title: String::from("Blocked Accounts"),
+            actors,
+            action,
+            more: None,
         })
     } else {
-        FluffTemplate::from(ProfileView {
+        FluffTemplate::from(ActorsView {
             session: None,
-            profile: None,
+            title: String::from("Blocked Accounts"),
+            actors: Vec::new(),
+            action,
+            more: None,
         })
     }
 }
 
-pub async fn follow(c: Client) -> Fluff {
-    let profile = c.parameter("profile").unwrap();
+pub async fn words(c: Client) -> FluffTemplate<WordsView> {
+    if let Some(fingerprint) = c.fingerprint() {
+        let session = c.state.sessions.get(&fingerprint).unwrap();
+        let words = session.clone().muted_words().await.unwrap();
 
+        FluffTemplate::from(WordsView {
+            session: Some(session.handle.clone()),
+            words,
+        })
+    } else {
+        FluffTemplate::from(WordsView {
+            session: None,
+            words: Vec::new(),
+        })
+    }
+}
+
+pub async fn mute_word(c: Client) -> Fluff {
     if let Some(fingerprint) = c.fingerprint() {
-        c.state
-            .clone()
-            .sessions
-            .get(&fingerprint)
-            .unwrap()
-            .clone()
-            .follow(profile)
+        let Some(input) = c.input() else {
+            return Fluff::Input(
+                "word or phrase to mute, optionally followed by \";\" and any of \"tags\" (only match hashtags), \"24h\", \"7d\", \"30d\" (expiry), \"notfollowing\" (only from accounts you don't follow)"
+                    .to_string(),
+            );
+        };
+
+        let (value, flags) = input.split_once(';').unwrap_or((&input, ""));
+        let mut tags_only = false;
+        let mut duration = None;
+        let mut not_following = false;
+        for flag in flags.split_whitespace() {
+            match flag {
+                "tags" => tags_only = true,
+                "24h" => duration = Some(TimeDelta::hours(24)),
+                "7d" => duration = Some(TimeDelta::days(7)),
+                "30d" => duration = Some(TimeDelta::days(30)),
+                "notfollowing" => not_following = true,
+                _ => return Fluff::Input(format!("unknown option \"{flag}\"; try again")),
+            }
+        }
+
+        let value = value.trim();
+        if value.is_empty() {
+            return Fluff::Input("the muted word can't be empty; try again".to_string());
+        }
+
+        let session = c.state.clone().sessions.get(&fingerprint).unwrap().clone();
+        session
+            .mute_word(value, tags_only, duration, not_following)
             .await
             .unwrap();
     }
 
-    Fluff::RedirectTemporary(format!("/@{}", profile))
+    Fluff::RedirectTemporary("/m/words".to_string())
+}
+
+pub async fn unmute_word(c: Client) -> Fluff {
+    if let Some(fingerprint) = c.fingerprint() {
+        let Some(input) = c.input() else {
+            return Fluff::Input("word or phrase to unmute".to_string());
+        };
+
+        let session = c.state.clone().sessions.get(&fingerprint).unwrap().clone();
+        session.unmute_word(&input).await.unwrap();
+    }
+
+    Fluff::RedirectTemporary("/m/words".to_string())
+}
+
+pub async fn moderation(c: Client) -> FluffTemplate<ModerationView> {
+    if let Some(fingerprint) = c.fingerprint() {
+        let session = c.state.sessions.get(&fingerprint).unwrap();
+        let labelers = session.clone().labelers().await.unwrap();
+
+        FluffTemplate::from(ModerationView {
+            session: Some(session.handle.clone()),
+            labelers,
+        })
+    } else {
+        FluffTemplate::from(ModerationView {
+            session: None,
+            labelers: Vec::new(),
+        })
+    }
+}
+
+pub async fn report(c: Client) -> Fluff {
+    let (base, done) = match (c.parameter("id"), c.parameter("profile")) {
+        (Some(id), _) => (format!("/p/{id}/x"), String::from("/")),
+        (_, Some(profile)) => (format!("/@{profile}/x"), format!("/@{profile}")),
+        _ => return Fluff::NotFound(String::from("Nothing to report.")),
+    };
+
+    if let Some(fingerprint) = c.fingerprint() {
+        let Some(reason) = c.parameter("reason") else {
+            let Some(input) = c.input() else {
+                return Fluff::Input(
+                    "why are you reporting this? \"spam\", \"violation\", \"misleading\", \"sexual\", \"rude\" or \"other\""
+                        .to_string(),
+                );
+            };
+
+            return match moderation::reason_type(input.trim()) {
+                Some(_) => Fluff::RedirectTemporary(format!("{base}/{}", input.trim())),
+                None => Fluff::Input(format!("unknown reason \"{input}\"; try again")),
+            };
+        };
+
+        let Some(reason_type) = moderation::reason_type(reason) else {
+            return Fluff::RedirectTemporary(base);
+        };
+        let Some(input) = c.input() else {
+            return Fluff::Input(
+                "add any details for the moderators, or \"-\" to skip".to_string(),
+            );
+        };
+        let comment = Some(input.trim()).filter(|i| *i != "-");
+
+        let session = c.state.clone().sessions.get(&fingerprint).unwrap().clone();
+        let result = match (c.parameter("id"), c.parameter("profile")) {
+            (Some(id), _) => session.report_post(id, reason_type, comment).await,
+            (_, Some(profile)) => session.report_account(profile, reason_type, comment).await,
+            _ => Ok(()),
+        };
+
+        if let Err(e) = result {
+            return Fluff::FailurePermanent(e.to_string());
+        }
+    }
+
+    Fluff::RedirectTemporary(done)
 }
 
 pub async fn interact(c: Client) -> Fluff {
     if let Some(fingerprint) = c.fingerprint() {
         let Some(input) = c.input() else {
             return Fluff::Input(
-                "usage: \"l\" to like, \"r\" to repost, \"R\" to reply".to_string(),
+                "usage: \"l\" to like, \"r\" to repost, \"R\" to reply, \"O\" to reply with options, \"g\" to change reply and quote controls, \"m\" to mute or unmute the thread, \"p\" to pin or unpin your own post, \"v\" to see likes, reposts and quotes, \"x\" to report"
+                    .to_string(),
             );
         };
 
@@ -86,9 +1542,32 @@ pub async fn interact(c: Client) -> Fluff {
         let session = c.state.clone().sessions.get(&fingerprint).unwrap().clone();
 
         match input.as_str() {
-            "l" => session.like(&id).await.unwrap(),
-            "r" => session.repost(&id).await.unwrap(),
+            "l" => session.like(id).await.unwrap(),
+            "r" => session.repost(id).await.unwrap(),
             "R" => return Fluff::RedirectTemporary(format!("/p/{id}/r")),
+            "O" => return Fluff::RedirectTemporary(format!("/p/{id}/o")),
+            "g" => return Fluff::RedirectTemporary(format!("/p/{id}/g")),
+            "m" => {
+                if let Err(e) = session.mute_thread(id).await {
+                    return Fluff::FailurePermanent(e.to_string());
+                }
+            }
+            "p" => {
+                let profile = format!("/@{}", session.handle);
+                return match session.pin(id).await {
+                    Ok(()) => Fluff::RedirectTemporary(profile),
+                    Err(e) => Fluff::FailurePermanent(e.to_string()),
+                };
+            }
+            // The permalink keeps the post's content warning, unlike
+            // clicking through it.
+            "v" => {
+                return match session.post_path(id).await {
+                    Ok(path) => Fluff::RedirectTemporary(path),
+                    Err(e) => Fluff::FailurePermanent(e.to_string()),
+                }
+            }
+            "x" => return Fluff::RedirectTemporary(format!("/p/{id}/x")),
             _ => (),
         }
     }
@@ -96,30 +1575,258 @@ pub async fn interact(c: Client) -> Fluff {
     Fluff::RedirectTemporary("/".to_string())
 }
 
+pub async fn mute_thread(c: Client) -> Fluff {
+    if let Some(fingerprint) = c.fingerprint() {
+        let id = c.parameter("id").unwrap();
+        let session = c.state.clone().sessions.get(&fingerprint).unwrap().clone();
+
+        if let Err(e) = session.mute_thread(id).await {
+            return Fluff::FailurePermanent(e.to_string());
+        }
+    }
+
+    Fluff::RedirectTemporary("/".to_string())
+}
+
 pub async fn reply(c: Client) -> Fluff {
     if let Some(fingerprint) = c.fingerprint() {
+        let id = c.parameter("id").unwrap();
+        let options = match c.parameter("options").map(str::parse::<PostOptions>) {
+            Some(Ok(options)) => options,
+            Some(Err(e)) => return retry_options(&c, &format!("/p/{id}/r"), e),
+            None => PostOptions::default(),
+        };
         let Some(input) = c.input() else {
             return Fluff::Input("write your reply here".to_string());
         };
+        match lang_query(&c) {
+            Some(Ok(lang)) => return Fluff::RedirectTemporary(format!("/p/{id}/r/lang:{lang}")),
+            Some(Err(e)) => {
+                return Fluff::Input(format!(
+                    "{e}; write your reply here to use your default languages instead"
+                ))
+            }
+            None => (),
+        }
+
+        let session = c.state.clone().sessions.get(&fingerprint).unwrap().clone();
+        session.reply(id, &input, &options).await.unwrap();
+    };
+
+    Fluff::RedirectTemporary("/".to_string())
+}
+
+pub async fn gate(c: Client) -> Fluff {
+    if let Some(fingerprint) = c.fingerprint() {
+        let Some(input) = c.input() else {
+            return Fluff::Input(OPTIONS_USAGE.to_string());
+        };
+        let options = match input.parse::<PostOptions>() {
+            Ok(options) => options,
+            Err(e) => return Fluff::Input(format!("{e}; {OPTIONS_USAGE}")),
+        };
 
         let id = c.parameter("id").unwrap();
         let session = c.state.clone().sessions.get(&fingerprint).unwrap().clone();
 
-        session.reply(id, &input).await.unwrap();
+        if let Err(e) = session.gate(id, &options).await {
+            return Fluff::FailurePermanent(e.to_string());
+        }
     };
 
     Fluff::RedirectTemporary("/".to_string())
 }
 
+pub async fn post_options(c: Client) -> Fluff {
+    let Some(input) = c.input() else {
+        return Fluff::Input(OPTIONS_USAGE.to_string());
+    };
+
+    match input.parse::<PostOptions>() {
+        Ok(options) => match c.parameter("id") {
+            Some(id) => Fluff::RedirectTemporary(format!("/p/{id}/r/{options}")),
+            None => Fluff::RedirectTemporary(format!("/p/o/{options}")),
+        },
+        Err(e) => Fluff::Input(format!("{e}; {OPTIONS_USAGE}")),
+    }
+}
+
 pub async fn post(c: Client) -> Fluff {
     if let Some(fingerprint) = c.fingerprint() {
+        let options = match c.parameter("options").map(str::parse::<PostOptions>) {
+            Some(Ok(options)) => options,
+            Some(Err(e)) => return retry_options(&c, "/p/o", e),
+            None => PostOptions::default(),
+        };
         let Some(input) = c.input() else {
             return Fluff::Input("write your post here".to_string());
         };
+        match lang_query(&c) {
+            Some(Ok(lang)) => return Fluff::RedirectTemporary(format!("/p/o/lang:{lang}")),
+            Some(Err(e)) => {
+                return Fluff::Input(format!(
+                    "{e}; write your post here to use your default languages instead"
+                ))
+            }
+            None => (),
+        }
 
         let session = c.state.clone().sessions.get(&fingerprint).unwrap().clone();
-        session.post(&input).await.unwrap();
+        session.post(&input, &options).await.unwrap();
     };
 
     Fluff::RedirectTemporary("/".to_string())
 }
+
+pub async fn langs(c: Client) -> Fluff {
+    if let Some(fingerprint) = c.fingerprint() {
+        let session = c.state.clone().sessions.get(&fingerprint).unwrap().clone();
+
+        let Some(input) = c.input() else {
+            let current = session.langs().await;
+            return Fluff::Input(format!(
+                "languages for new posts (currently: {}), up to 3, e.g. \"en\" or \"en de\"; \"auto\" to detect them",
+                if current.is_empty() {
+                    String::from("auto")
+                } else {
+                    current.join(" ")
+                }
+            ));
+        };
+
+        let langs: Vec<String> = if input.trim() == "auto" {
+            Vec::new()
+        } else {
+            input.split_whitespace().map(String::from).collect()
+        };
+        if let Some(invalid) = langs.iter().find(|l| Language::from_str(l).is_err()) {
+            return Fluff::Input(format!("invalid language \"{invalid}\"; try again"));
+        }
+        if langs.len() > 3 {
+            return Fluff::Input(String::from(
+                "a post can have at most 3 languages; try again",
+            ));
+        }
+
+        session.set_langs(langs).await.unwrap();
+    }
+
+    Fluff::RedirectTemporary("/".to_string())
+}
+
+/// Finds the session to serve a page with: the user's own, or the read-only
+/// one for visitors when guest mode is enabled. The handle is only given for
+/// signed in users.
+fn reader(c: &Client) -> Option<(Session, Option<String>)> {
+    match c.fingerprint().and_then(|f| c.state.sessions.get(&f)) {
+        Some(session) => Some((session.clone(), Some(session.handle.clone()))),
+        None => c.state.guest.clone().map(|guest| (guest, None)),
+    }
+}
+
+/// Same as [`reader`] for subscriptions, leaving guests out of the home
+/// timeline, which needs a signed in user.
+fn subscriber(c: &Client) -> Option<(Session, Option<String>)> {
+    let home = c.parameter("profile").is_none() && c.parameter("did").is_none();
+    reader(c).filter(|(_, handle)| handle.is_some() || !home)
+}
+
+/// Fetches the posts behind a subscription: a profile's or a custom feed's,
+/// or the user's home timeline when neither is given. Returns them along
+/// with a title and the path of the page they come from.
+async fn subscription(c: &Client, session: Session) -> (String, String, Vec<Post>) {
+    match (
+        c.parameter("profile"),
+        c.parameter("did"),
+        c.parameter("rkey"),
+    ) {
+        (Some(profile), _, _) => (
+            format!("Posts by @{profile}"),
+            format!("/@{profile}"),
+            session.author_feed(profile).await.unwrap(),
+        ),
+        (_, Some(did), Some(rkey)) => {
+            let (generator, posts, _) = session.custom_feed(did, rkey, None).await.unwrap();
+            (
+                format!("{} by @{}", generator.name, generator.creator),
+                generator.path(),
+                posts,
+            )
+        }
+        _ => (
+            format!("Home timeline of @{}", session.handle),
+            String::new(),
+            session.feed().await.unwrap(),
+        ),
+    }
+}
+
+/// Redirects to the path given as input, which lets list pages send users
+/// back to where they were after an action, or to `default` otherwise. Only
+/// local paths are followed.
+fn back(c: &Client, default: String) -> Fluff {
+    match c.input() {
+        Some(path) if path.starts_with('/') && !path.starts_with("//") => {
+            Fluff::RedirectTemporary(path)
+        }
+        _ => Fluff::RedirectTemporary(default),
+    }
+}
+
+/// Builds an absolute URL to `path` on this server.
+fn server_url(c: &Client, scheme: &str, path: &str) -> String {
+    let host = c.url.host_str().unwrap_or("localhost");
+    match c.url.port() {
+        Some(port) => format!("{scheme}://{host}:{port}{path}"),
+        None => format!("{scheme}://{host}{path}"),
+    }
+}
+
+/// Decodes a hashtag from its path segment, which fluffer leaves
+/// percent-encoded, dropping any leading `#`.
+fn tag_name(segment: &str) -> Option<String> {
+    let tag = urlencoding::decode(segment).ok()?;
+    Some(tag.trim_start_matches('#').to_string())
+}
+
+/// Returns the language from a `?lang=<code>` query, which lets a post or
+/// reply be started in a given language without going through the options
+/// prompt. Languages which aren't valid are returned as an error.
+fn lang_query(c: &Client) -> Option<Result<String, String>> {
+    if !c.url.query()?.starts_with("lang=") {
+        return None;
+    }
+
+    let lang = c.query("lang")?;
+    Some(match Language::from_str(&lang) {
+        Ok(_) => Ok(lang),
+        Err(_) => Err(format!("invalid language \"{lang}\"")),
+    })
+}
+
+/// Asks for the options of a post again when the ones in its path can't be
+/// parsed, rather than posting it without them.
+fn retry_options(c: &Client, base: &str, error: String) -> Fluff {
+    match c.input().map(|i| i.parse::<PostOptions>()) {
+        Some(Ok(options)) => Fluff::RedirectTemporary(format!("{base}/{options}")),
+        Some(Err(e)) => Fluff::Input(format!("{e}; {OPTIONS_USAGE}")),
+        None => Fluff::Input(format!("{error}; {OPTIONS_USAGE}")),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tag_name_decodes_non_ascii_tags() {
+        assert_eq!(tag_name("%E6%97%A5%E6%9C%AC").as_deref(), Some("日本"));
+        assert_eq!(tag_name("%23rust").as_deref(), Some("rust"));
+        assert_eq!(tag_name("gemini").as_deref(), Some("gemini"));
+    }
+
+    #[test]
+    fn tag_name_rejects_invalid_utf8() {
+        assert_eq!(tag_name("%FF"), None);
+    }
+}